@@ -2,11 +2,40 @@ module main
 
 import os
 import parsers
+import time
+
+// StdinProtocol selects how analyze_stdin reads files from standard input
+pub enum StdinProtocol {
+	paths  // one file path per line, files are read from disk
+	inline // `path\0contents\0` records, nothing is read from disk
+}
+
+pub fn parse_stdin_protocol(name string) !StdinProtocol {
+	match name {
+		'paths' { return .paths }
+		'inline' { return .inline }
+		else { return error('Unknown stdin protocol: ${name} (expected paths or inline)') }
+	}
+}
+
+// RunStats describes the throughput of the most recent analyze_directory or
+// analyze_stdin call. Only files that parsed successfully are counted.
+pub struct RunStats {
+pub mut:
+	files         int
+	total_bytes   i64
+	total_micros  i64 // wall clock for the whole run, including the directory walk or stdin reads
+	read_micros   i64 // time spent reading files from disk
+	parse_micros  i64 // time spent in the language parsers
+	bytes_per_sec f64
+}
 
 pub struct Analyzer {
 pub mut:
 	parsers_map map[string]parsers.Parser
 	target_lang string
+mut:
+	stats RunStats
 }
 
 pub fn new_analyzer() Analyzer {
@@ -113,8 +142,15 @@ fn (mut a Analyzer) register_parsers() {
 	}
 }
 
+// last_run_stats returns the timings of the most recent analysis run
+pub fn (a Analyzer) last_run_stats() RunStats {
+	return a.stats
+}
+
 pub fn (mut a Analyzer) analyze_directory(root_path string, mut progress ProgressTracker) []parsers.ParseResult {
 	mut results := []parsers.ParseResult{}
+	a.stats = RunStats{}
+	run_watch := time.new_stopwatch()
 
 	// Get all files to process
 	files := a.collect_files(root_path)
@@ -123,7 +159,7 @@ pub fn (mut a Analyzer) analyze_directory(root_path string, mut progress Progres
 	for file_path in files {
 		progress.report_file(file_path)
 
-		result := a.analyze_file(file_path) or {
+		result := a.analyze_file_timed(file_path) or {
 			progress.report_error(file_path, err.msg())
 			continue
 		}
@@ -133,9 +169,80 @@ pub fn (mut a Analyzer) analyze_directory(root_path string, mut progress Progres
 		}
 	}
 
+	a.finish_stats(run_watch)
 	return results
 }
 
+// analyze_stdin analyzes files named or carried on standard input and writes
+// each result as soon as it is parsed. Files without a registered parser are
+// skipped silently since pipelines rarely filter by extension.
+pub fn (mut a Analyzer) analyze_stdin(protocol StdinProtocol, mut progress ProgressTracker, mut writer OutputWriter) ! {
+	a.stats = RunStats{}
+	run_watch := time.new_stopwatch()
+	defer {
+		a.finish_stats(run_watch)
+	}
+
+	match protocol {
+		.paths {
+			for {
+				line := os.get_raw_line()
+				if line.len == 0 {
+					break
+				}
+
+				file_path := line.trim_space()
+				if file_path.len == 0 || os.file_ext(file_path) !in a.parsers_map {
+					continue
+				}
+
+				progress.report_file(file_path)
+				result := a.analyze_file_timed(file_path) or {
+					progress.report_error(file_path, err.msg())
+					continue
+				}
+				writer.write_result(result)!
+			}
+		}
+		.inline {
+			raw := os.get_raw_stdin().bytestr()
+			terminator := u8(0).ascii_str()
+			// Every record ends with a NUL, so anything else is cut off mid-contents
+			if raw.len > 0 && !raw.ends_with(terminator) {
+				return error('Truncated inline record: input does not end with a NUL byte')
+			}
+
+			mut fields := raw.split(terminator)
+			// The record terminator leaves an empty field after the last record
+			if fields.len > 0 && fields.last().len == 0 {
+				fields.delete_last()
+			}
+			if fields.len % 2 != 0 {
+				return error('Truncated inline record for: ${fields.last()}')
+			}
+
+			// Only count the records that will be analyzed so progress reaches its total
+			mut supported := []int{}
+			for i := 0; i < fields.len; i += 2 {
+				if os.file_ext(fields[i]) in a.parsers_map {
+					supported << i
+				}
+			}
+
+			progress.total_files = supported.len
+			for i in supported {
+				file_path := fields[i]
+				progress.report_file(file_path)
+				result := a.analyze_content_timed(file_path, fields[i + 1]) or {
+					progress.report_error(file_path, err.msg())
+					continue
+				}
+				writer.write_result(result)!
+			}
+		}
+	}
+}
+
 fn (a Analyzer) collect_files(root_path string) []string {
 	mut files := []string{}
 	a.walk_directory(root_path, mut files)
@@ -166,14 +273,44 @@ fn (a Analyzer) walk_directory(dir_path string, mut files []string) {
 	}
 }
 
-pub fn (a Analyzer) analyze_file(file_path string) !parsers.ParseResult {
+// analyze_content parses source text that is already in memory
+pub fn (a Analyzer) analyze_content(file_path string, content string) !parsers.ParseResult {
 	ext := os.file_ext(file_path)
 
 	parser := a.parsers_map[ext] or { return error('No parser found for extension: ${ext}') }
 
+	return parser.parse(content, file_path)
+}
+
+// analyze_file_timed reads and parses a file, adding its read and parse times to the run stats
+fn (mut a Analyzer) analyze_file_timed(file_path string) !parsers.ParseResult {
+	ext := os.file_ext(file_path)
+	if ext !in a.parsers_map {
+		return error('No parser found for extension: ${ext}')
+	}
+
+	read_watch := time.new_stopwatch()
 	content := os.read_file(file_path) or { return error('Failed to read file: ${err}') }
+	a.stats.read_micros += read_watch.elapsed().microseconds()
 
-	return parser.parse(content, file_path)
+	return a.analyze_content_timed(file_path, content)
+}
+
+// analyze_content_timed is analyze_content with its parse time added to the run stats
+fn (mut a Analyzer) analyze_content_timed(file_path string, content string) !parsers.ParseResult {
+	parse_watch := time.new_stopwatch()
+	result := a.analyze_content(file_path, content)!
+	a.stats.parse_micros += parse_watch.elapsed().microseconds()
+	a.stats.files++
+	a.stats.total_bytes += content.len
+	return result
+}
+
+fn (mut a Analyzer) finish_stats(run_watch time.StopWatch) {
+	a.stats.total_micros = run_watch.elapsed().microseconds()
+	if a.stats.total_micros > 0 {
+		a.stats.bytes_per_sec = f64(a.stats.total_bytes) * 1_000_000.0 / f64(a.stats.total_micros)
+	}
 }
 
 pub fn (a Analyzer) get_supported_extensions() []string {
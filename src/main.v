@@ -5,13 +5,16 @@ import flag
 
 struct Arguments {
 mut:
-	input     string
-	lang      string
-	output    string
-	config    string
-	verbose   bool
-	show_line bool
-	help      bool
+	input       string
+	lang        string
+	output      string
+	config      string
+	verbose     bool
+	show_line   bool
+	line_format string
+	format      string
+	stdin       string
+	help        bool
 }
 
 fn main() {
@@ -23,20 +26,22 @@ fn main() {
 	}
 
 	// Validate required arguments
-	if args.input.len == 0 {
-		eprintln('Error: --input is required')
-		print_help()
-		exit(1)
-	}
+	if args.stdin.len == 0 {
+		if args.input.len == 0 {
+			eprintln('Error: --input is required')
+			print_help()
+			exit(1)
+		}
 
-	if !os.exists(args.input) {
-		eprintln('Error: Input path does not exist: ${args.input}')
-		exit(1)
-	}
+		if !os.exists(args.input) {
+			eprintln('Error: Input path does not exist: ${args.input}')
+			exit(1)
+		}
 
-	if !os.is_dir(args.input) {
-		eprintln('Error: Input path must be a directory: ${args.input}')
-		exit(1)
+		if !os.is_dir(args.input) {
+			eprintln('Error: Input path must be a directory: ${args.input}')
+			exit(1)
+		}
 	}
 
 	// Load config if provided
@@ -50,6 +55,16 @@ fn main() {
 		}
 	}
 
+	span_formatter := new_span_formatter(args.line_format) or {
+		eprintln('Error: ${err}')
+		exit(1)
+	}
+
+	output_format := parse_output_format(args.format) or {
+		eprintln('Error: ${err}')
+		exit(1)
+	}
+
 	// Initialize analyzer
 	mut analyzer := new_analyzer()
 	if args.lang.len > 0 {
@@ -61,24 +76,46 @@ fn main() {
 	progress.init(args.verbose, 0)
 
 	if args.verbose {
-		eprintln('Starting analysis of: ${args.input}')
+		source := if args.stdin.len > 0 { 'standard input' } else { args.input }
+		eprintln('Starting analysis of: ${source}')
 		extensions := analyzer.get_supported_extensions()
 		eprintln('Supported extensions: ${extensions.join(', ')}')
 	}
 
-	// Analyze directory
-	results := analyzer.analyze_directory(args.input, mut progress)
+	if args.stdin.len > 0 {
+		protocol := parse_stdin_protocol(args.stdin) or {
+			eprintln('Error: ${err}')
+			exit(1)
+		}
 
-	// Write output
-	write_output(results, args.output, args.show_line) or {
-		eprintln('Error writing output: ${err}')
-		exit(1)
+		// Analyze files from stdin, writing each one as it completes
+		mut writer := new_output_writer(args.output, args.show_line, span_formatter, output_format) or {
+			eprintln('Error writing output: ${err}')
+			exit(1)
+		}
+		analyzer.analyze_stdin(protocol, mut progress, mut writer) or {
+			writer.close()
+			eprintln('Error reading stdin: ${err}')
+			exit(1)
+		}
+		writer.close()
+	} else {
+		// Analyze directory
+		results := analyzer.analyze_directory(args.input, mut progress)
+
+		// Write output
+		write_output(results, args.output, args.show_line, span_formatter, output_format) or {
+			eprintln('Error writing output: ${err}')
+			exit(1)
+		}
 	}
 
 	// Print summary
 	progress.print_summary()
 
 	if args.verbose {
+		stats := analyzer.last_run_stats()
+		eprintln('Throughput: ${stats.total_bytes} bytes in ${stats.total_micros} us (${stats.bytes_per_sec:.0f} bytes/s; read ${stats.read_micros} us, parse ${stats.parse_micros} us)')
 		eprintln('Output written to: ${args.output}')
 	}
 
@@ -94,12 +131,15 @@ fn parse_arguments() Arguments {
 
 	mut args := Arguments{}
 
-	args.input = fp.string('input', `i`, '', 'Root directory path (required)')
+	args.input = fp.string('input', `i`, '', 'Root directory path (required unless --stdin)')
 	args.lang = fp.string('lang', `l`, '', 'Programming language filter (optional)')
 	args.output = fp.string('output', `o`, './output.txt', 'Output file path')
 	args.config = fp.string('config', `c`, '', 'Custom config file path')
 	args.verbose = fp.bool('verbose', `v`, false, 'Show progress and details')
 	args.show_line = fp.bool('line', `n`, false, 'Show line numbers for code elements')
+	args.line_format = fp.string('line-format', `f`, 'plain', 'Location format for --line: plain, rustc, msvc, emacs')
+	args.format = fp.string('format', 0, 'text', 'Output format: text, ndjson')
+	args.stdin = fp.string('stdin', `s`, '', 'Read files from stdin instead of --input: paths, inline')
 	args.help = fp.bool('help', `h`, false, 'Show help message')
 
 	fp.finalize() or {
@@ -119,12 +159,16 @@ Usage:
   code-analyzer --input <path> [options]
 
 Arguments:
-  -i, --input <path>      Root directory path (required)
+  -i, --input <path>      Root directory path (required unless --stdin)
   -l, --lang <language>   Programming language filter (optional)
   -o, --output <file>     Output file path (default: ./output.txt)
   -c, --config <file>     Custom config file path (YAML or JSON)
   -v, --verbose           Show progress and details
   -n, --line              Show line numbers for code elements
+  -f, --line-format <fmt> Location format for --line: plain, rustc, msvc, emacs
+                          (default: plain)
+      --format <fmt>      Output format: text, ndjson (default: text)
+  -s, --stdin <protocol>  Read files from stdin instead of --input: paths, inline
   -h, --help              Show this help message
 
 Supported Languages:
@@ -158,6 +202,15 @@ Examples:
 
   # Use custom config for additional languages
   code-analyzer --input ./src --config ./custom.yaml --verbose
+
+  # Analyze the files listed by another tool
+  find . -name "*.rs" | code-analyzer --stdin paths --output results.txt
+
+  # Print clickable locations for an editor that understands rustc output
+  code-analyzer --input ./src --line --line-format rustc
+
+  # Stream one JSON object per element for jq or a log shipper
+  code-analyzer --input ./src --format ndjson --output elements.ndjson
 '
 	println(help_text)
 }
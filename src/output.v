@@ -1,38 +1,113 @@
 module main
 
 import os
+import json
 import parsers
 
-pub fn write_output(results []parsers.ParseResult, output_path string, show_line bool) ! {
-	mut f := os.create(output_path) or { return error('Failed to create output file: ${err}') }
-	defer {
-		f.close()
+// OutputFormat selects how OutputWriter renders elements
+pub enum OutputFormat {
+	text   // file header followed by one line per element
+	ndjson // one JSON object per element per line
+}
+
+pub fn parse_output_format(name string) !OutputFormat {
+	match name {
+		'text' { return .text }
+		'ndjson' { return .ndjson }
+		else { return error('Unknown output format: ${name} (expected text or ndjson)') }
 	}
+}
 
-	for result in results {
-		if result.elements.len == 0 {
-			continue
-		}
+// NdjsonRecord is the object written for each element in ndjson output
+struct NdjsonRecord {
+	file   string
+	line   int
+	kind   string
+	name   string
+	access string
+	parent string
+	doc    string
+}
 
-		// Write file path
-		f.write_string('${result.file_path}\n') or {
-			return error('Failed to write to output file: ${err}')
-		}
+// OutputWriter appends analysis results to the output file one file at a time
+pub struct OutputWriter {
+mut:
+	file           os.File
+	show_line      bool
+	span_formatter SpanFormatter
+	format         OutputFormat
+}
+
+pub fn new_output_writer(output_path string, show_line bool, span_formatter SpanFormatter, format OutputFormat) !OutputWriter {
+	file := os.create(output_path) or { return error('Failed to create output file: ${err}') }
+	return OutputWriter{
+		file:           file
+		show_line:      show_line
+		span_formatter: span_formatter
+		format:         format
+	}
+}
+
+pub fn (mut w OutputWriter) write_result(result parsers.ParseResult) ! {
+	if result.elements.len == 0 {
+		return
+	}
 
-		// Write elements
+	if w.format == .ndjson {
 		for element in result.elements {
-			line := format_element(element, show_line)
-			f.write_string('${line}\n') or {
+			record := NdjsonRecord{
+				file:   result.file_path
+				line:   element.line_number
+				kind:   element.element_type
+				name:   element.name
+				access: element.access
+				parent: element.parent
+				doc:    element.doc
+			}
+			w.file.write_string('${json.encode(record)}\n') or {
 				return error('Failed to write to output file: ${err}')
 			}
 		}
+		w.file.flush()
+		return
+	}
+
+	// Write file path
+	w.file.write_string('${result.file_path}\n') or {
+		return error('Failed to write to output file: ${err}')
+	}
+
+	// Write elements
+	for element in result.elements {
+		line := format_element(element, result.file_path, w.show_line, w.span_formatter)
+		w.file.write_string('${line}\n') or {
+			return error('Failed to write to output file: ${err}')
+		}
+	}
+
+	// Add blank line between files
+	w.file.write_string('\n') or { return error('Failed to write to output file: ${err}') }
 
-		// Add blank line between files
-		f.write_string('\n') or { return error('Failed to write to output file: ${err}') }
+	// Make each file visible to readers as soon as it is written
+	w.file.flush()
+}
+
+pub fn (mut w OutputWriter) close() {
+	w.file.close()
+}
+
+pub fn write_output(results []parsers.ParseResult, output_path string, show_line bool, span_formatter SpanFormatter, format OutputFormat) ! {
+	mut writer := new_output_writer(output_path, show_line, span_formatter, format)!
+	defer {
+		writer.close()
+	}
+
+	for result in results {
+		writer.write_result(result)!
 	}
 }
 
-fn format_element(element parsers.CodeElement, show_line bool) string {
+fn format_element(element parsers.CodeElement, file_path string, show_line bool, span_formatter SpanFormatter) string {
 	mut parts := []string{}
 
 	// Handle different element types
@@ -85,7 +160,9 @@ fn format_element(element parsers.CodeElement, show_line bool) string {
 	}
 
 	if show_line {
-		return '${element.line_number}: ${final_output}'
+		// Parsers record lines only, so every location points at column 1
+		location := span_formatter.format(file_path, element.line_number, 1)
+		return '${location}: ${final_output}'
 	}
 
 	return final_output
@@ -18,7 +18,12 @@ pub fn (mut p ProgressTracker) init(verbose bool, total int) {
 pub fn (mut p ProgressTracker) report_file(file_path string) {
 	p.files_processed++
 	if p.verbose {
-		eprintln('Processing [${p.files_processed}/${p.total_files}]: ${file_path}')
+		// The total is unknown while paths are still streaming in
+		if p.total_files > 0 {
+			eprintln('Processing [${p.files_processed}/${p.total_files}]: ${file_path}')
+		} else {
+			eprintln('Processing [${p.files_processed}]: ${file_path}')
+		}
 	}
 }
 
@@ -0,0 +1,46 @@
+module main
+
+// SpanFormatter renders the source location printed in front of each element
+// when line numbers are enabled. Lines and columns passed in are 1-based.
+pub interface SpanFormatter {
+	format(file_path string, line int, column int) string
+}
+
+// PlainSpanFormatter prints only the line number, e.g. `12`
+pub struct PlainSpanFormatter {}
+
+pub fn (f PlainSpanFormatter) format(file_path string, line int, column int) string {
+	return '${line}'
+}
+
+// RustcSpanFormatter prints `file:line:column`, e.g. `src/lib.rs:12:1`
+pub struct RustcSpanFormatter {}
+
+pub fn (f RustcSpanFormatter) format(file_path string, line int, column int) string {
+	return '${file_path}:${line}:${column}'
+}
+
+// MsvcSpanFormatter prints `file(line,column)`, e.g. `src/lib.rs(12,1)`
+pub struct MsvcSpanFormatter {}
+
+pub fn (f MsvcSpanFormatter) format(file_path string, line int, column int) string {
+	return '${file_path}(${line},${column})'
+}
+
+// EmacsSpanFormatter prints the GNU `file:line.column` form, e.g. `src/lib.rs:12.1`
+pub struct EmacsSpanFormatter {}
+
+pub fn (f EmacsSpanFormatter) format(file_path string, line int, column int) string {
+	return '${file_path}:${line}.${column}'
+}
+
+// new_span_formatter returns the built-in formatter registered under name
+pub fn new_span_formatter(name string) !SpanFormatter {
+	match name {
+		'plain' { return PlainSpanFormatter{} }
+		'rustc' { return RustcSpanFormatter{} }
+		'msvc' { return MsvcSpanFormatter{} }
+		'emacs' { return EmacsSpanFormatter{} }
+		else { return error('Unknown line format: ${name} (expected plain, rustc, msvc or emacs)') }
+	}
+}
@@ -0,0 +1,46 @@
+#!/bin/sh
+# Stand-in external processor for the CommandProcessor tests in
+# test/sample_code/document_processor.rs
+# usage: command_plugin.sh <mode> [document id]
+
+input=$(cat)
+
+case "$1" in
+completed)
+    echo '{"status": "completed"}'
+    ;;
+echo-input)
+    # Hands the document back as an artifact so tests can check the stdin contract
+    escaped=$(printf '%s' "$input" | sed 's/\\/\\\\/g; s/"/\\"/g')
+    printf '{"status": "completed", "artifacts": {"id": "%s", "input": "%s"}}\n' "$2" "$escaped"
+    ;;
+metadata)
+    cat <<'JSON'
+{"status": "completed", "warnings": ["short content", "no headings"],
+ "metadata": {"language": "de", "tags": ["plugin"], "custom_fields": {"reviewed": "yes"}},
+ "artifacts": {"summary": "A summary"}}
+JSON
+    ;;
+failed)
+    echo '{"status": "failed", "message": "bad input", "warnings": ["checked 0 rules"]}'
+    ;;
+exit-code)
+    echo 'something broke' >&2
+    exit 3
+    ;;
+flood)
+    head -c 4096 /dev/zero | tr '\0' 'x'
+    ;;
+hang)
+    sleep 10
+    ;;
+background)
+    # The background job inherits stdout, so the pipe stays open after this shell exits
+    sleep 10 &
+    echo '{"status": "completed"}'
+    ;;
+*)
+    echo "unknown mode: $1" >&2
+    exit 64
+    ;;
+esac
@@ -1,6 +1,13 @@
 // Document Processing System
 // Demonstrates Rust structs, enums, traits, and methods
 
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex, Weak};
+
 /// Document types supported by the system
 #[derive(Debug, Clone, PartialEq)]
 pub enum DocumentType {
@@ -9,10 +16,20 @@ pub enum DocumentType {
     Html,
     Pdf,
     Word,
+    Json,
+    Code(CodeLanguage),
+}
+
+/// Programming languages recognized in source code documents
+#[derive(Debug, Clone, PartialEq)]
+pub enum CodeLanguage {
+    Rust,
+    Python,
+    JavaScript,
 }
 
 /// Document processing status
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ProcessingStatus {
     Pending,
     Processing,
@@ -20,6 +37,211 @@ pub enum ProcessingStatus {
     Failed(String),
 }
 
+/// Record of a single processor run on a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessingRecord {
+    pub processor_name: String,
+    pub status: ProcessingStatus,
+    pub timestamp: std::time::SystemTime,
+    pub duration_ms: u64,
+}
+
+/// Processing state selected by `find_by_processing_status`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProcessingStatusFilter {
+    Completed,
+    Failed,
+    /// Never run by the processor, or last seen pending or in progress
+    Pending,
+    Any,
+}
+
+/// Aggregated run counts and timings for one processor
+#[derive(Debug, Clone, Default)]
+pub struct ProcessorSummary {
+    pub completed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub total_duration_ms: u64,
+}
+
+/// Processing history summarized across all documents
+#[derive(Debug, Clone, Default)]
+pub struct ProcessingReport {
+    pub by_processor: HashMap<String, ProcessorSummary>,
+}
+
+impl ProcessingReport {
+    /// Prints the report as an ASCII table sorted by processor name
+    pub fn print_table(&self) {
+        let mut names: Vec<&String> = self.by_processor.keys().collect();
+        names.sort();
+
+        let width = names.iter().map(|name| name.len()).max().unwrap_or(0).max("Processor".len());
+        let separator = format!("+-{}-+-----------+--------+---------+---------------+", "-".repeat(width));
+
+        println!("{}", separator);
+        println!(
+            "| {:<width$} | Completed | Failed | Skipped | Duration (ms) |",
+            "Processor",
+            width = width
+        );
+        println!("{}", separator);
+        for name in names {
+            let summary = &self.by_processor[name];
+            println!(
+                "| {:<width$} | {:>9} | {:>6} | {:>7} | {:>13} |",
+                name,
+                summary.completed,
+                summary.failed,
+                summary.skipped,
+                summary.total_duration_ms,
+                width = width
+            );
+        }
+        println!("{}", separator);
+    }
+}
+
+/// Corpus-wide text metrics returned by `text_statistics`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextStatistics {
+    pub total_characters: usize,
+    pub total_words: usize,
+    pub total_sentences: usize,
+    /// Words per sentence
+    pub average_sentence_length: f64,
+    /// Distinct normalized words
+    pub vocabulary_size: usize,
+    /// `vocabulary_size / total_words`
+    pub type_token_ratio: f64,
+}
+
+/// Errors returned when processing a single document on demand
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProcessError {
+    DocumentNotFound(String),
+    ProcessorNotFound(String),
+    ProcessingFailed(String),
+}
+
+/// Errors returned when copying a document inside a manager
+#[derive(Debug, Clone, PartialEq)]
+pub enum CopyError {
+    SourceNotFound(String),
+    DestinationExists(String),
+}
+
+/// Errors returned when comparing two documents
+#[derive(Debug, Clone, PartialEq)]
+pub enum OverlapError {
+    DocumentNotFound(String),
+}
+
+/// Errors returned when moving a document between managers
+#[derive(Debug, Clone, PartialEq)]
+pub enum MoveError {
+    NotFound(String),
+    DuplicateId(String),
+}
+
+/// Error returned when document references form a cycle
+#[derive(Debug, Clone, PartialEq)]
+pub struct CycleError {
+    /// IDs of the documents on the cycle, each referencing the next
+    pub document_ids: Vec<String>,
+}
+
+/// Errors returned when deriving a new document from an existing one
+#[derive(Debug, Clone, PartialEq)]
+pub enum DocumentError {
+    EmptyId,
+    /// An annotation's `start..end` is not a valid byte range of the content
+    InvalidAnnotationRange(usize, usize),
+}
+
+/// Errors returned when moving a document to the trash
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeleteError {
+    NotFound(String),
+    AlreadyDeleted(String),
+}
+
+/// Errors returned when restoring a document from the trash
+#[derive(Debug, Clone, PartialEq)]
+pub enum RestoreError {
+    NotDeleted(String),
+    DuplicateId(String),
+}
+
+/// Kind of change made to a managed document
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// Notification delivered to callbacks registered with `watch_for_changes`
+#[derive(Debug, Clone)]
+pub struct ChangeEvent {
+    pub kind: ChangeKind,
+    pub document_id: String,
+    pub timestamp: std::time::SystemTime,
+}
+
+/// Callback registered with `watch_for_changes`
+type ChangeCallback = Arc<dyn Fn(ChangeEvent) + Send + Sync>;
+
+/// Change callbacks shared between a manager and its watch handles
+#[derive(Default)]
+struct ChangeWatchers {
+    next_id: u64,
+    callbacks: Vec<(u64, ChangeCallback)>,
+}
+
+/// Registration returned by `watch_for_changes`
+/// Dropping the handle keeps the callback registered; call `stop` to remove it.
+pub struct WatchHandle {
+    watchers: Weak<Mutex<ChangeWatchers>>,
+    id: u64,
+}
+
+impl WatchHandle {
+    /// Deregisters the callback
+    pub fn stop(self) {
+        if let Some(watchers) = self.watchers.upgrade() {
+            let mut watchers = watchers.lock().unwrap_or_else(|e| e.into_inner());
+            watchers.callbacks.retain(|(id, _)| *id != self.id);
+        }
+    }
+}
+
+/// Kind of tag change reported to tag subscribers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TagEventKind {
+    Added,
+    Removed,
+}
+
+/// Notification delivered to callbacks registered with `subscribe_to_tag_events`
+#[derive(Debug, Clone)]
+pub struct TagEvent {
+    pub kind: TagEventKind,
+    pub document_id: String,
+    pub tag: String,
+}
+
+/// Callback registered with `subscribe_to_tag_events`
+type TagCallback = Box<dyn Fn(TagEvent)>;
+
+/// Common English words skipped by vocabulary statistics
+pub const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have", "he", "her",
+    "his", "i", "in", "is", "it", "its", "not", "of", "on", "or", "she", "that", "the", "their", "them",
+    "they", "this", "to", "was", "we", "were", "will", "with", "you", "your",
+];
+
 /// Base document structure
 #[derive(Debug, Clone)]
 pub struct Document {
@@ -28,16 +250,62 @@ pub struct Document {
     pub content: String,
     pub doc_type: DocumentType,
     pub created_at: std::time::SystemTime,
+    pub last_modified: std::time::SystemTime,
     pub metadata: DocumentMetadata,
 }
 
+/// Reviewer note attached to a byte range of the content
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub start: usize,
+    pub end: usize,
+    pub author: String,
+    pub note: String,
+}
+
+/// Selects which internal fields are removed before sharing a document
+#[derive(Debug, Clone, Copy)]
+pub struct ExportOptions {
+    pub strip_history: bool,
+    pub strip_annotations: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            strip_history: true,
+            strip_annotations: true,
+        }
+    }
+}
+
+/// Summary of the changes made by `Document::anonymize`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnonymizationReport {
+    /// Pseudonym that replaced the document author
+    pub pseudonym: String,
+    pub annotations_anonymized: usize,
+}
+
 /// Document metadata information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct DocumentMetadata {
     pub author: String,
     pub word_count: usize,
     pub language: String,
     pub tags: Vec<String>,
+    pub processing_history: Vec<ProcessingRecord>,
+    pub custom_fields: HashMap<String, String>,
+    pub annotations: Vec<Annotation>,
+    pub source_path: Option<std::path::PathBuf>,
+    pub code: CodeMetadata,
+}
+
+/// Metrics stored for source code documents
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CodeMetadata {
+    /// Set by `CodeAnalyzer::update_maintainability_index`
+    pub maintainability_index: Option<f64>,
 }
 
 impl Document {
@@ -57,24 +325,94 @@ impl Document {
             word_count,
             language: "en".to_string(), // Default language
             tags: Vec::new(),
+            processing_history: Vec::new(),
+            custom_fields: HashMap::new(),
+            annotations: Vec::new(),
+            source_path: None,
+            code: CodeMetadata::default(),
         };
 
+        let now = std::time::SystemTime::now();
         Document {
             id,
             title,
             content,
             doc_type,
-            created_at: std::time::SystemTime::now(),
+            created_at: now,
+            last_modified: now,
             metadata,
         }
     }
 
+    /// Creates a document whose ID is the slug of its title
+    /// The title is the first non-empty line of the content, without any
+    /// leading Markdown heading markers.
+    /// # Arguments
+    /// * `content` - Document content
+    /// * `doc_type` - Type of document
+    /// * `author` - Document author
+    /// # Returns
+    /// New Document instance
+    pub fn with_slug_id(content: String, doc_type: DocumentType, author: String) -> Document {
+        let title = content
+            .lines()
+            .map(|line| line.trim().trim_start_matches('#').trim())
+            .find(|line| !line.is_empty())
+            .unwrap_or_default()
+            .to_string();
+
+        let mut document = Document::new(String::new(), title, content, doc_type, author);
+        document.id = document.generate_slug();
+        document
+    }
+
+    /// Loads a document from a file
+    /// The ID is the file stem, the title the file name, and the type follows
+    /// the extension (plain text when unknown).
+    /// # Arguments
+    /// * `path` - File to read
+    /// # Returns
+    /// Document remembering its source path, or the read error
+    pub fn from_path(path: &std::path::Path) -> Result<Self, std::io::Error> {
+        let content = std::fs::read_to_string(path)?;
+        let name_of = |part: Option<&std::ffi::OsStr>| part.map(|p| p.to_string_lossy().into_owned()).unwrap_or_default();
+        let extension = name_of(path.extension()).to_lowercase();
+        let doc_type = match extension.as_str() {
+            "md" | "markdown" => DocumentType::Markdown,
+            "html" | "htm" => DocumentType::Html,
+            "json" => DocumentType::Json,
+            "rs" => DocumentType::Code(CodeLanguage::Rust),
+            "py" => DocumentType::Code(CodeLanguage::Python),
+            "js" => DocumentType::Code(CodeLanguage::JavaScript),
+            _ => DocumentType::Text,
+        };
+
+        let mut document = Document::new(name_of(path.file_stem()), name_of(path.file_name()), content, doc_type, String::new());
+        document.metadata.source_path = Some(path.to_path_buf());
+        Ok(document)
+    }
+
+    /// Re-reads the content from the file the document was loaded from
+    /// # Returns
+    /// Ok after refreshing content and word count, or an error if there is no source path
+    pub fn reload(&mut self) -> Result<(), std::io::Error> {
+        let path = self.metadata.source_path.as_ref().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, format!("Document {} has no source path", self.id))
+        })?;
+
+        self.content = std::fs::read_to_string(path)?;
+        self.update_word_count();
+        self.touch();
+        Ok(())
+    }
+
     /// Adds a tag to the document
     /// # Arguments
     /// * `tag` - Tag to add
     pub fn add_tag(&mut self, tag: String) {
         if !self.metadata.tags.contains(&tag) {
             self.metadata.tags.push(tag);
+            self.touch();
         }
     }
 
@@ -86,6 +424,7 @@ impl Document {
     pub fn remove_tag(&mut self, tag: &str) -> bool {
         if let Some(index) = self.metadata.tags.iter().position(|t| t == tag) {
             self.metadata.tags.remove(index);
+            self.touch();
             true
         } else {
             false
@@ -117,6 +456,318 @@ impl Document {
         self.title.to_lowercase().contains(&search_term.to_lowercase())
             || self.content.to_lowercase().contains(&search_term.to_lowercase())
     }
+
+    /// Removes fenced and inline code from Markdown content
+    /// Fenced blocks are dropped with their ``` fences (an unclosed fence runs
+    /// to the end), and `inline` spans are dropped within a line. An inline
+    /// span closes at the next backtick run of the same length, so ``a`b`` is
+    /// one span; unmatched backticks are kept. The number of removed fenced
+    /// blocks is stored in `custom_fields["stripped_code_blocks"]` and the
+    /// number of inline spans in `custom_fields["stripped_inline_code"]`.
+    pub fn strip_code_blocks(&mut self) {
+        let mut kept_lines = Vec::new();
+        let mut in_fence = false;
+        let mut removed = 0;
+        let mut removed_inline = 0;
+
+        for line in self.content.lines() {
+            if line.trim_start().starts_with("```") {
+                if !in_fence {
+                    removed += 1;
+                }
+                in_fence = !in_fence;
+                continue;
+            }
+            if in_fence {
+                continue;
+            }
+
+            let mut kept = String::with_capacity(line.len());
+            let mut rest = line;
+            while let Some(open) = rest.find('`') {
+                let run = backtick_run(&rest[open..]);
+                let after = &rest[open + run..];
+                let mut search = 0;
+                let mut close = None;
+                while let Some(found) = after[search..].find('`') {
+                    let start = search + found;
+                    let length = backtick_run(&after[start..]);
+                    if length == run {
+                        close = Some(start);
+                        break;
+                    }
+                    search = start + length;
+                }
+
+                match close {
+                    Some(start) => {
+                        kept.push_str(&rest[..open]);
+                        rest = &after[start + run..];
+                        removed_inline += 1;
+                    }
+                    None => {
+                        kept.push_str(&rest[..open + run]);
+                        rest = after;
+                    }
+                }
+            }
+            kept.push_str(rest);
+            kept_lines.push(kept);
+        }
+
+        self.content = kept_lines.join("\n");
+        self.update_word_count();
+        self.touch();
+        self.metadata
+            .custom_fields
+            .insert("stripped_code_blocks".to_string(), removed.to_string());
+        self.metadata
+            .custom_fields
+            .insert("stripped_inline_code".to_string(), removed_inline.to_string());
+    }
+
+    /// Renders the document's fields as a two-column Markdown table
+    /// Content is left out; times are seconds since the Unix epoch.
+    /// # Returns
+    /// CommonMark table with `Field` and `Value` columns
+    pub fn as_markdown_table(&self) -> String {
+        let seconds = |time: std::time::SystemTime| {
+            time.duration_since(std::time::UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or(0)
+        };
+
+        let mut custom_fields: Vec<String> = self
+            .metadata
+            .custom_fields
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        custom_fields.sort();
+
+        let history: Vec<String> = self
+            .metadata
+            .processing_history
+            .iter()
+            .map(|record| format!("{} ({:?})", record.processor_name, record.status))
+            .collect();
+
+        let annotations: Vec<String> = self
+            .metadata
+            .annotations
+            .iter()
+            .map(|annotation| {
+                format!("{}..{} {}: {}", annotation.start, annotation.end, annotation.author, annotation.note)
+            })
+            .collect();
+
+        let rows = [
+            ("ID", self.id.clone()),
+            ("Title", self.title.clone()),
+            ("Type", format!("{:?}", self.doc_type)),
+            ("Created At", seconds(self.created_at).to_string()),
+            ("Last Modified", seconds(self.last_modified).to_string()),
+            ("Author", self.metadata.author.clone()),
+            ("Word Count", self.metadata.word_count.to_string()),
+            ("Language", self.metadata.language.clone()),
+            ("Tags", self.metadata.tags.join(", ")),
+            ("Processing History", history.join(", ")),
+            ("Custom Fields", custom_fields.join(", ")),
+            ("Annotations", annotations.join(", ")),
+            (
+                "Maintainability Index",
+                self.metadata
+                    .code
+                    .maintainability_index
+                    .map(|index| format!("{:.1}", index))
+                    .unwrap_or_default(),
+            ),
+            (
+                "Source Path",
+                self.metadata
+                    .source_path
+                    .as_ref()
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_default(),
+            ),
+        ];
+
+        let mut table = String::from("| Field | Value |\n| --- | --- |\n");
+        for (field, value) in rows {
+            table.push_str(&format!("| {} | {} |\n", field, escape_table_cell(&value)));
+        }
+        table
+    }
+
+    /// Makes a copy of the document that is safe to share externally
+    /// `strip_history` clears the processing history together with the
+    /// processor-written custom fields; `strip_annotations` clears annotations.
+    /// The local source path is always removed.
+    /// # Arguments
+    /// * `options` - Fields to strip
+    /// # Returns
+    /// Stripped copy; the original is unchanged
+    pub fn strip_metadata_for_export(&self, options: &ExportOptions) -> Document {
+        let mut export = self.clone();
+        export.metadata.source_path = None;
+        if options.strip_history {
+            export.metadata.processing_history.clear();
+            export.metadata.custom_fields.clear();
+        }
+        if options.strip_annotations {
+            export.metadata.annotations.clear();
+        }
+        export
+    }
+
+    /// Forks the document under a new identity
+    /// The copy keeps content, tags and annotations but starts with no processing history.
+    /// # Arguments
+    /// * `new_id` - ID of the copy
+    /// # Returns
+    /// The copy, or an error if the ID is empty or an annotation does not fit the content
+    pub fn clone_with_new_id(&self, new_id: String) -> Result<Document, DocumentError> {
+        if new_id.is_empty() {
+            return Err(DocumentError::EmptyId);
+        }
+        if let Some(annotation) = self.metadata.annotations.iter().find(|annotation| {
+            annotation.start > annotation.end
+                || !self.content.is_char_boundary(annotation.start)
+                || !self.content.is_char_boundary(annotation.end)
+        }) {
+            return Err(DocumentError::InvalidAnnotationRange(annotation.start, annotation.end));
+        }
+
+        let now = std::time::SystemTime::now();
+        let mut copy = self.clone();
+        copy.id = new_id;
+        copy.created_at = now;
+        copy.last_modified = now;
+        copy.metadata.processing_history.clear();
+        Ok(copy)
+    }
+
+    /// Removes author identities before the document is shared
+    /// Authors are replaced by `Author_<hash>`, where the hash is the first
+    /// 8 hex digits of the SHA-256 of the name, so the same person keeps
+    /// the same pseudonym across documents. The source path is cleared.
+    /// # Returns
+    /// Pseudonym of the document author and the number of annotations changed
+    pub fn anonymize(&mut self) -> AnonymizationReport {
+        let pseudonym = author_pseudonym(&self.metadata.author);
+        self.metadata.author = pseudonym.clone();
+        for annotation in &mut self.metadata.annotations {
+            annotation.author = author_pseudonym(&annotation.author);
+        }
+        self.metadata.source_path = None;
+        self.touch();
+
+        AnonymizationReport {
+            pseudonym,
+            annotations_anonymized: self.metadata.annotations.len(),
+        }
+    }
+
+    /// Builds a URL-friendly identifier from the title
+    /// Lowercases the title, turns spaces into `-`, drops everything except
+    /// letters, digits (including non-ASCII ones such as `ü`) and `-`, collapses
+    /// `-` runs and trims `-` from both ends.
+    /// # Returns
+    /// Slug such as `quarterly-report-2024` or `ünïcode-2024`
+    pub fn generate_slug(&self) -> String {
+        let mut slug = String::with_capacity(self.title.len());
+        for c in self.title.to_lowercase().chars() {
+            let c = if c == ' ' { '-' } else { c };
+            if c == '-' && (slug.is_empty() || slug.ends_with('-')) {
+                continue;
+            }
+            if c.is_alphanumeric() || c == '-' {
+                slug.push(c);
+            }
+        }
+
+        slug.trim_end_matches('-').to_string()
+    }
+
+    /// Counts how often each word occurs in the content
+    /// Words are lowercased with surrounding punctuation trimmed.
+    /// # Returns
+    /// Map from word to number of occurrences
+    pub fn word_frequency_map(&self) -> HashMap<String, usize> {
+        word_frequencies(&self.content)
+    }
+
+    /// Records that the document changed just now
+    fn touch(&mut self) {
+        self.last_modified = std::time::SystemTime::now();
+    }
+
+    /// Checks if a processor has already completed on this document
+    /// # Arguments
+    /// * `processor_name` - Name of the processor
+    /// # Returns
+    /// true if the history contains a `Completed` record from the processor
+    pub fn is_processed_by(&self, processor_name: &str) -> bool {
+        self.metadata.processing_history.iter().any(|record| {
+            record.processor_name == processor_name
+                && matches!(record.status, ProcessingStatus::Completed)
+        })
+    }
+
+    /// Re-detects the document type from its content
+    /// HTML markers win over JSON, JSON over source code, and source code
+    /// over Markdown; anything else is plain text.
+    /// # Returns
+    /// The detected type, which is also stored in `doc_type`
+    pub fn auto_detect_type(&mut self) -> DocumentType {
+        let trimmed = self.content.trim();
+        let lowered = trimmed.to_lowercase();
+
+        let detected = if lowered.contains("<!doctype html") || lowered.contains("<html") {
+            DocumentType::Html
+        } else if (trimmed.starts_with('{') || trimmed.starts_with('['))
+            && JsonValue::parse(trimmed).is_ok()
+        {
+            DocumentType::Json
+        } else if let Some(language) = detect_code_language(trimmed) {
+            DocumentType::Code(language)
+        } else if trimmed.lines().any(|line| line.trim_start().starts_with("# ")) || trimmed.contains("**") {
+            DocumentType::Markdown
+        } else {
+            DocumentType::Text
+        };
+
+        if self.doc_type != detected {
+            self.doc_type = detected.clone();
+            self.touch();
+        }
+        detected
+    }
+
+    /// Splits the document into page-sized child documents
+    /// A trailing newline ends the last line rather than starting an empty one.
+    /// # Arguments
+    /// * `lines_per_page` - Number of lines per page (0 is treated as 1)
+    /// # Returns
+    /// Pages with IDs `{id}-page-{n}` (1-based); the last page may be shorter
+    pub fn split_into_pages(&self, lines_per_page: usize) -> Vec<Document> {
+        let content = self.content.strip_suffix('\n').unwrap_or(&self.content);
+        let lines: Vec<&str> = content.split('\n').collect();
+
+        lines
+            .chunks(lines_per_page.max(1))
+            .enumerate()
+            .map(|(index, chunk)| {
+                Document::new(
+                    format!("{}-page-{}", self.id, index + 1),
+                    self.title.clone(),
+                    chunk.join("\n"),
+                    self.doc_type.clone(),
+                    self.metadata.author.clone(),
+                )
+            })
+            .collect()
+    }
 }
 
 /// Document processor trait
@@ -126,19 +777,42 @@ pub trait DocumentProcessor {
     /// * `document` - Document to process
     /// # Returns
     /// Processing result with status
-    fn process(&self, document: &Document) -> Result<ProcessingStatus, String>;
+    fn process(&self, document: &mut Document) -> Result<ProcessingStatus, String>;
     
     /// Gets processor name
     /// # Returns
     /// Processor name
     fn name(&self) -> &str;
+
+    /// Checks whether the processor handles a document type
+    /// # Arguments
+    /// * `doc_type` - Document type to check
+    /// # Returns
+    /// true if documents of this type can be processed (defaults to all types)
+    fn supports(&self, _doc_type: &DocumentType) -> bool {
+        true
+    }
+
+    /// Gets the priority shown in pipeline diagrams
+    /// # Returns
+    /// Priority, higher is more important (defaults to 0)
+    fn priority(&self) -> i32 {
+        0
+    }
+
+    /// Gets the processors a dispatching processor routes documents to
+    /// # Returns
+    /// `(condition, processor)` pairs, empty for processors that do the work themselves
+    fn branches(&self) -> Vec<(String, &dyn DocumentProcessor)> {
+        Vec::new()
+    }
 }
 
 /// Text document processor
 pub struct TextProcessor;
 
 impl DocumentProcessor for TextProcessor {
-    fn process(&self, document: &Document) -> Result<ProcessingStatus, String> {
+    fn process(&self, document: &mut Document) -> Result<ProcessingStatus, String> {
         println!("Processing text document: {}", document.title);
         
         if document.content.is_empty() {
@@ -154,13 +828,17 @@ impl DocumentProcessor for TextProcessor {
     fn name(&self) -> &str {
         "TextProcessor"
     }
+
+    fn supports(&self, doc_type: &DocumentType) -> bool {
+        *doc_type == DocumentType::Text
+    }
 }
 
 /// HTML document processor with validation
 pub struct HtmlProcessor;
 
 impl DocumentProcessor for HtmlProcessor {
-    fn process(&self, document: &Document) -> Result<ProcessingStatus, String> {
+    fn process(&self, document: &mut Document) -> Result<ProcessingStatus, String> {
         println!("Processing HTML document: {}", document.title);
         
         if !document.content.contains("<html>") && !document.content.contains("<!DOCTYPE") {
@@ -176,99 +854,3474 @@ impl DocumentProcessor for HtmlProcessor {
     fn name(&self) -> &str {
         "HtmlProcessor"
     }
-}
 
-/// Document manager for handling multiple documents
-pub struct DocumentManager {
-    documents: Vec<Document>,
-    processors: Vec<Box<dyn DocumentProcessor>>,
+    fn supports(&self, doc_type: &DocumentType) -> bool {
+        *doc_type == DocumentType::Html
+    }
 }
 
-impl DocumentManager {
-    /// Creates a new document manager
-    pub fn new() -> Self {
-        DocumentManager {
-            documents: Vec::new(),
-            processors: Vec::new(),
+/// Markdown document processor
+pub struct MarkdownProcessor;
+
+impl DocumentProcessor for MarkdownProcessor {
+    fn process(&self, document: &mut Document) -> Result<ProcessingStatus, String> {
+        println!("Processing Markdown document: {}", document.title);
+
+        if document.content.trim().is_empty() {
+            return Err("Document content is empty".to_string());
         }
+
+        // Simulate processing time
+        std::thread::sleep(std::time::Duration::from_millis(100));
+
+        Ok(ProcessingStatus::Completed)
     }
 
-    /// Adds a document processor
-    /// # Arguments
-    /// * `processor` - Processor to add
-    pub fn add_processor(&mut self, processor: Box<dyn DocumentProcessor>) {
-        self.processors.push(processor);
+    fn name(&self) -> &str {
+        "MarkdownProcessor"
     }
 
-    /// Adds a document to the manager
-    /// # Arguments
-    /// * `document` - Document to add
-    pub fn add_document(&mut self, document: Document) {
-        self.documents.push(document);
+    fn supports(&self, doc_type: &DocumentType) -> bool {
+        *doc_type == DocumentType::Markdown
     }
+}
 
-    /// Finds documents by author
-    /// # Arguments
-    /// * `author` - Author name to search for
-    /// # Returns
-    /// Vector of matching documents
-    pub fn find_by_author(&self, author: &str) -> Vec<&Document> {
-        self.documents
-            .iter()
-            .filter(|doc| doc.metadata.author.to_lowercase() == author.to_lowercase())
-            .collect()
+/// Processor that delegates to an external executable
+///
+/// # Protocol
+/// The document is written to the child's stdin as one JSON object:
+/// `{"id": "...", "title": "...", "content": "...", "doc_type": "Text",
+/// "author": "...", "tags": ["..."]}`, after which stdin is closed.
+///
+/// The child must print one JSON object to stdout:
+/// `{"status": "completed" | "failed" | "pending", "message": "...", "warnings": ["..."],
+/// "metadata": {"language": "...", "tags": ["..."], "custom_fields": {"key": "value"}},
+/// "artifacts": {"name": "value"}}`.
+/// Only `status` is required and unknown keys are ignored.
+/// - `message` is the failure reason for `failed`.
+/// - `warnings` are stored one per line in the custom field `<name>.warnings`,
+///   which is removed when a run reports none. They are kept for failed runs too.
+/// - `metadata` is applied only when the status is not `failed`: `language`
+///   replaces the document language, `tags` are added, and `custom_fields`
+///   are merged into the document's custom fields. Author, title and content
+///   cannot be changed.
+/// - `artifacts` are stored as the custom fields `<name>.artifact.<artifact name>`,
+///   also only when the status is not `failed`.
+///
+/// Every value inside `warnings`, `metadata` and `artifacts` must be a string.
+///
+/// A non-zero exit code is a failure carrying the captured stderr. Output
+/// larger than `max_output_bytes` is a failure. A run that has not finished
+/// after `timeout`, including processes the child left running with its
+/// output still open, is a failure; the child itself is killed. Calling
+/// `cancel` on a handle from `cancel_handle` ends a running child the same way
+/// and makes every later run fail without starting the program.
+///
+/// The placeholder `{id}` in `args` and `env` values is replaced with the
+/// document ID before the child is spawned.
+pub struct CommandProcessor {
+    pub name: String,
+    pub program: String,
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    pub timeout: std::time::Duration,
+    pub max_output_bytes: usize,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Cancels the runs of a `CommandProcessor`, possibly from another thread
+#[derive(Debug, Clone)]
+pub struct CancelHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelHandle {
+    /// Kills the running child, if any, and stops later runs from starting
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
     }
+}
 
-    /// Finds documents by type
+impl CommandProcessor {
+    /// Creates a command processor with a 30 second timeout and 1 MiB output limit
     /// # Arguments
-    /// * `doc_type` - Document type to search for
-    /// # Returns
-    /// Vector of matching documents
-    pub fn find_by_type(&self, doc_type: &DocumentType) -> Vec<&Document> {
-        self.documents
-            .iter()
-            .filter(|doc| &doc.doc_type == doc_type)
-            .collect()
+    /// * `name` - Processor name
+    /// * `program` - Executable to run
+    /// * `args` - Arguments, may contain `{id}`
+    pub fn new(name: String, program: String, args: Vec<String>) -> Self {
+        CommandProcessor {
+            name,
+            program,
+            args,
+            env: Vec::new(),
+            timeout: std::time::Duration::from_secs(30),
+            max_output_bytes: 1024 * 1024,
+            cancelled: Arc::default(),
+        }
     }
 
-    /// Processes all documents using available processors
+    /// Gets a handle that cancels this processor's runs
     /// # Returns
-    /// Vector of processing results
-    pub fn process_all_documents(&self) -> Vec<Result<ProcessingStatus, String>> {
-        let mut results = Vec::new();
-        
-        for document in &self.documents {
-            for processor in &self.processors {
-                let result = processor.process(document);
-                results.push(result);
-            }
+    /// Handle shared by every clone
+    pub fn cancel_handle(&self) -> CancelHandle {
+        CancelHandle {
+            cancelled: self.cancelled.clone(),
         }
-        
-        results
     }
 
-    /// Gets total number of documents
+    /// Runs the child process and collects its exit status, stdout and stderr
+    fn run(&self, document: &Document) -> Result<(std::process::ExitStatus, Vec<u8>, Vec<u8>), String> {
+        use std::io::{Read, Write};
+        use std::process::{Command, Stdio};
+
+        if self.cancelled.load(Ordering::SeqCst) {
+            return Err(format!("{} was cancelled", self.program));
+        }
+
+        let expand = |value: &str| value.replace("{id}", &document.id);
+        let mut child = Command::new(&self.program)
+            .args(self.args.iter().map(|arg| expand(arg)))
+            .envs(self.env.iter().map(|(key, value)| (key.clone(), expand(value))))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", self.program, e))?;
+
+        // The pipe threads are never joined: a process the child started may
+        // keep a pipe open long after the child has exited
+        let input = document_to_json(document);
+        let mut stdin = child.stdin.take().ok_or("Failed to open child stdin")?;
+        std::thread::spawn(move || {
+            // A child that exits without reading its input is not an error here
+            let _ = stdin.write_all(input.as_bytes());
+        });
+
+        let limit = self.max_output_bytes as u64 + 1;
+        let read_pipe = |mut pipe: Box<dyn Read + Send>| {
+            let (sender, receiver) = mpsc::channel();
+            std::thread::spawn(move || {
+                let mut buffer = Vec::new();
+                let _ = (&mut pipe).take(limit).read_to_end(&mut buffer);
+                let _ = sender.send(buffer);
+            });
+            receiver
+        };
+        let stdout = read_pipe(Box::new(child.stdout.take().ok_or("Failed to open child stdout")?));
+        let stderr = read_pipe(Box::new(child.stderr.take().ok_or("Failed to open child stderr")?));
+
+        let deadline = std::time::Instant::now() + self.timeout;
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) => {
+                    if let Some(reason) = self.stop_reason(deadline) {
+                        let _ = child.kill();
+                        let _ = child.wait();
+                        return Err(reason);
+                    }
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                }
+                Err(e) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(format!("Failed to wait for {}: {}", self.program, e));
+                }
+            }
+        };
+
+        let stdout = self.receive_output(&stdout, deadline)?;
+        let stderr = self.receive_output(&stderr, deadline)?;
+        if stdout.len() > self.max_output_bytes {
+            return Err(format!("{} wrote more than {} bytes", self.program, self.max_output_bytes));
+        }
+
+        Ok((status, stdout, stderr))
+    }
+
+    /// Explains why a run has to stop early, if it does
+    fn stop_reason(&self, deadline: std::time::Instant) -> Option<String> {
+        if self.cancelled.load(Ordering::SeqCst) {
+            Some(format!("{} was cancelled", self.program))
+        } else if std::time::Instant::now() >= deadline {
+            Some(format!("{} timed out after {:?}", self.program, self.timeout))
+        } else {
+            None
+        }
+    }
+
+    /// Waits for a pipe reader until the pipe closes, the deadline passes or the run is cancelled
+    fn receive_output(&self, receiver: &mpsc::Receiver<Vec<u8>>, deadline: std::time::Instant) -> Result<Vec<u8>, String> {
+        loop {
+            match receiver.recv_timeout(std::time::Duration::from_millis(10)) {
+                Ok(buffer) => return Ok(buffer),
+                Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(Vec::new()),
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    if let Some(reason) = self.stop_reason(deadline) {
+                        return Err(reason);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Copies the metadata updates and artifacts of a successful run into the document
+    fn apply_output(&self, output: &JsonValue, document: &mut Document) -> Result<(), String> {
+        if let Some(metadata) = output.get("metadata") {
+            if let Some(language) = metadata.get("language") {
+                let language = language.as_str().ok_or("metadata.language must be a string")?;
+                document.metadata.language = language.to_string();
+            }
+            if let Some(tags) = metadata.get("tags") {
+                for tag in json_strings(tags, "metadata.tags")? {
+                    document.add_tag(tag.to_string());
+                }
+            }
+            if let Some(fields) = metadata.get("custom_fields") {
+                for (key, value) in json_string_map(fields, "metadata.custom_fields")? {
+                    document.metadata.custom_fields.insert(key.to_string(), value.to_string());
+                }
+            }
+        }
+
+        if let Some(artifacts) = output.get("artifacts") {
+            for (name, value) in json_string_map(artifacts, "artifacts")? {
+                document
+                    .metadata
+                    .custom_fields
+                    .insert(format!("{}.artifact.{}", self.name, name), value.to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DocumentProcessor for CommandProcessor {
+    fn process(&self, document: &mut Document) -> Result<ProcessingStatus, String> {
+        let (status, stdout, stderr) = self.run(document)?;
+
+        if !status.success() {
+            return Err(format!(
+                "{} exited with {}: {}",
+                self.program,
+                status,
+                String::from_utf8_lossy(&stderr).trim()
+            ));
+        }
+
+        let output = String::from_utf8(stdout).map_err(|_| "Command output is not UTF-8".to_string())?;
+        let result = JsonValue::parse(&output)?;
+
+        let warnings_key = format!("{}.warnings", self.name);
+        let warnings = match result.get("warnings") {
+            Some(warnings) => json_strings(warnings, "warnings")?,
+            None => Vec::new(),
+        };
+        if warnings.is_empty() {
+            document.metadata.custom_fields.remove(&warnings_key);
+        } else {
+            document.metadata.custom_fields.insert(warnings_key, warnings.join("\n"));
+        }
+
+        let message = result.get("message").and_then(JsonValue::as_str).unwrap_or("");
+        let status = match result.get("status").and_then(JsonValue::as_str) {
+            Some("completed") => ProcessingStatus::Completed,
+            Some("pending") => ProcessingStatus::Pending,
+            Some("failed") => return Err(message.to_string()),
+            Some(other) => return Err(format!("Unknown status in command output: {}", other)),
+            None => return Err("Command output has no status".to_string()),
+        };
+
+        self.apply_output(&result, document)?;
+        Ok(status)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Processor that turns `#hashtag` words into document tags
+pub struct HashTagExtractor;
+
+impl HashTagExtractor {
+    /// Finds hashtags matching `#[a-zA-Z][a-zA-Z0-9_]*`
+    /// # Arguments
+    /// * `content` - Text to scan
     /// # Returns
-    /// Document count
-    pub fn document_count(&self) -> usize {
-        self.documents.len()
+    /// Unique hashtags without the `#` prefix, in order of first appearance
+    pub fn extract(content: &str) -> Vec<String> {
+        let mut hashtags: Vec<String> = Vec::new();
+
+        for (index, _) in content.match_indices('#') {
+            let rest = &content[index + 1..];
+            if !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                continue;
+            }
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let hashtag = rest[..end].to_string();
+            if !hashtags.contains(&hashtag) {
+                hashtags.push(hashtag);
+            }
+        }
+
+        hashtags
     }
+}
 
-    /// Gets documents with specific tag
+impl DocumentProcessor for HashTagExtractor {
+    fn process(&self, document: &mut Document) -> Result<ProcessingStatus, String> {
+        for hashtag in Self::extract(&document.content) {
+            document.add_tag(hashtag);
+        }
+
+        Ok(ProcessingStatus::Completed)
+    }
+
+    fn name(&self) -> &str {
+        "HashTagExtractor"
+    }
+}
+
+/// Processor that collects academic-style citations from the content
+/// Bracketed keys such as `[1]`, `[2, 5]` or `[Smith2020]` are stored as
+/// written; parenthetical citations such as `(Smith, 2020)` or
+/// `(Smith et al., 2020; Doe, 2019)` are stored as `Smith 2020`.
+/// Markdown links (`[text](url)`) are not citations. A bracket only opens a
+/// citation at the start of the text or after whitespace or punctuation other
+/// than a closing bracket, so `arr[i]` and `m[0][1]` are skipped, and
+/// single-letter keys such as the `x` of a `- [x]` checkbox are rejected.
+pub struct CitationExtractor;
+
+impl CitationExtractor {
+    /// Finds every citation in order of appearance, including repeats
     /// # Arguments
-    /// * `tag` - Tag to search for
+    /// * `content` - Text to scan
     /// # Returns
-    /// Vector of documents with the tag
-    pub fn find_by_tag(&self, tag: &str) -> Vec<&Document> {
+    /// Citation keys, one per citation
+    pub fn extract(content: &str) -> Vec<String> {
+        let mut citations = Vec::new();
+
+        for (open, delimiter) in content.match_indices(['[', '(']) {
+            let close_char = if delimiter == "[" { ']' } else { ')' };
+            let rest = &content[open + 1..];
+            let Some(close) = rest.find(close_char) else {
+                continue;
+            };
+            let inner = &rest[..close];
+            if inner.contains(['[', '(', '\n']) {
+                continue;
+            }
+
+            if close_char == ']' {
+                if rest[close + 1..].starts_with('(') {
+                    continue;
+                }
+                let opens_citation = content[..open].chars().next_back().is_none_or(|c| {
+                    c.is_whitespace() || (c.is_ascii_punctuation() && !matches!(c, ']' | ')' | '_'))
+                });
+                if !opens_citation {
+                    continue;
+                }
+                let keys: Vec<&str> = inner.split([',', ';']).map(str::trim).collect();
+                let is_key = |key: &&str| {
+                    let mut chars = key.chars();
+                    let single_letter = matches!((chars.next(), chars.next()), (Some(c), None) if c.is_alphabetic());
+                    !key.is_empty()
+                        && !single_letter
+                        && key.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ':'))
+                };
+                if keys.iter().all(is_key) {
+                    citations.extend(keys.iter().map(|key| key.to_string()));
+                }
+            } else {
+                let parts: Vec<Option<String>> = inner.split(';').map(parse_author_year).collect();
+                if !parts.is_empty() && parts.iter().all(Option::is_some) {
+                    citations.extend(parts.into_iter().flatten());
+                }
+            }
+        }
+
+        citations
+    }
+}
+
+impl DocumentProcessor for CitationExtractor {
+    fn process(&self, document: &mut Document) -> Result<ProcessingStatus, String> {
+        let citations = Self::extract(&document.content);
+
+        let mut unique: Vec<&String> = Vec::new();
+        for citation in &citations {
+            if !unique.contains(&citation) {
+                unique.push(citation);
+            }
+        }
+
+        let keys: Vec<&str> = unique.iter().map(|key| key.as_str()).collect();
+        let fields = &mut document.metadata.custom_fields;
+        fields.insert("citations".to_string(), keys.join(","));
+        fields.insert("citation_count".to_string(), citations.len().to_string());
+
+        Ok(ProcessingStatus::Completed)
+    }
+
+    fn name(&self) -> &str {
+        "CitationExtractor"
+    }
+}
+
+/// Processor that routes documents to one of two processors by length
+pub struct SizeDispatchProcessor {
+    name: String,
+    small: Box<dyn DocumentProcessor>,
+    large: Box<dyn DocumentProcessor>,
+    threshold_words: usize,
+}
+
+impl SizeDispatchProcessor {
+    /// Creates a size dispatcher named after its targets and threshold,
+    /// e.g. `SizeDispatchProcessor(TextProcessor<500<=HashTagExtractor)`
+    /// # Arguments
+    /// * `small` - Processor for documents below the threshold
+    /// * `large` - Processor for documents at or above the threshold
+    /// * `threshold_words` - Word count that separates small from large
+    pub fn new(small: Box<dyn DocumentProcessor>, large: Box<dyn DocumentProcessor>, threshold_words: usize) -> Self {
+        let name = format!("SizeDispatchProcessor({}<{}<={})", small.name(), threshold_words, large.name());
+        SizeDispatchProcessor {
+            name,
+            small,
+            large,
+            threshold_words,
+        }
+    }
+
+    /// Picks the processor for a document
+    /// # Arguments
+    /// * `document` - Document to route
+    /// # Returns
+    /// `large` when the word count is at least the threshold, otherwise `small`
+    pub fn route(&self, document: &Document) -> &dyn DocumentProcessor {
+        if document.metadata.word_count < self.threshold_words {
+            self.small.as_ref()
+        } else {
+            self.large.as_ref()
+        }
+    }
+}
+
+impl DocumentProcessor for SizeDispatchProcessor {
+    fn process(&self, document: &mut Document) -> Result<ProcessingStatus, String> {
+        self.route(document).process(document)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn supports(&self, doc_type: &DocumentType) -> bool {
+        self.small.supports(doc_type) || self.large.supports(doc_type)
+    }
+
+    fn branches(&self) -> Vec<(String, &dyn DocumentProcessor)> {
+        vec![
+            (format!("< {} words", self.threshold_words), self.small.as_ref()),
+            (format!(">= {} words", self.threshold_words), self.large.as_ref()),
+        ]
+    }
+}
+
+/// Document manager for handling multiple documents
+pub struct DocumentManager {
+    documents: Vec<Document>,
+    processors: Vec<Box<dyn DocumentProcessor>>,
+    watchers: Arc<Mutex<ChangeWatchers>>,
+    /// Positions in `documents`, keyed by `created_at`
+    created_index: BTreeMap<std::time::SystemTime, Vec<usize>>,
+    word_frequency_cache: RefCell<Option<HashMap<String, usize>>>,
+    tag_subscribers: Vec<(String, TagCallback)>,
+    deleted: HashMap<String, Document>,
+    author_counts: BTreeMap<String, usize>,
+}
+
+impl DocumentManager {
+    /// Creates a new document manager
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Creates a document manager with room for a known number of documents
+    /// # Arguments
+    /// * `capacity` - Expected number of documents
+    pub fn with_capacity(capacity: usize) -> Self {
+        DocumentManager {
+            documents: Vec::with_capacity(capacity),
+            processors: Vec::new(),
+            watchers: Arc::default(),
+            created_index: BTreeMap::new(),
+            word_frequency_cache: RefCell::new(None),
+            tag_subscribers: Vec::new(),
+            deleted: HashMap::new(),
+            author_counts: BTreeMap::new(),
+        }
+    }
+
+    /// Reserves room for additional documents
+    /// # Arguments
+    /// * `additional` - Number of documents about to be added
+    pub fn reserve(&mut self, additional: usize) {
+        self.documents.reserve(additional);
+    }
+
+    /// Adds a document processor
+    /// # Arguments
+    /// * `processor` - Processor to add
+    pub fn add_processor(&mut self, processor: Box<dyn DocumentProcessor>) {
+        self.processors.push(processor);
+    }
+
+    /// Registers a pair of processors chosen by document length
+    /// # Arguments
+    /// * `small` - Processor for documents below the threshold
+    /// * `large` - Processor for documents at or above the threshold
+    /// * `threshold_words` - Word count that separates small from large
+    pub fn assign_processor_group_by_size(
+        &mut self,
+        small: Box<dyn DocumentProcessor>,
+        large: Box<dyn DocumentProcessor>,
+        threshold_words: usize,
+    ) {
+        self.add_processor(Box::new(SizeDispatchProcessor::new(small, large, threshold_words)));
+    }
+
+    /// Adds a document to the manager
+    /// # Arguments
+    /// * `document` - Document to add
+    pub fn add_document(&mut self, document: Document) {
+        let id = document.id.clone();
+        let tags = document.metadata.tags.clone();
+        self.created_index.entry(document.created_at).or_default().push(self.documents.len());
+        *self.author_counts.entry(document.metadata.author.clone()).or_insert(0) += 1;
+        self.documents.push(document);
+        self.word_frequency_cache.take();
+        notify_change(&self.watchers, ChangeKind::Added, &id);
+        for tag in &tags {
+            notify_tag(&self.tag_subscribers, TagEventKind::Added, &id, tag);
+        }
+    }
+
+    /// Adds a tag to a managed document
+    /// # Arguments
+    /// * `id` - ID of the document to tag
+    /// * `tag` - Tag to add
+    /// # Returns
+    /// true if the document exists and did not already have the tag
+    pub fn add_tag(&mut self, id: &str, tag: String) -> bool {
+        let Some(index) = self.index_of(id) else {
+            return false;
+        };
+        let document = &mut self.documents[index];
+        if document.metadata.tags.contains(&tag) {
+            return false;
+        }
+
+        document.add_tag(tag.clone());
+        self.word_frequency_cache.take();
+        notify_change(&self.watchers, ChangeKind::Modified, id);
+        notify_tag(&self.tag_subscribers, TagEventKind::Added, id, &tag);
+        true
+    }
+
+    /// Removes a tag from a managed document
+    /// # Arguments
+    /// * `id` - ID of the document to untag
+    /// * `tag` - Tag to remove
+    /// # Returns
+    /// true if the document exists and had the tag
+    pub fn remove_tag(&mut self, id: &str, tag: &str) -> bool {
+        let Some(index) = self.index_of(id) else {
+            return false;
+        };
+        if !self.documents[index].remove_tag(tag) {
+            return false;
+        }
+
+        self.word_frequency_cache.take();
+        notify_change(&self.watchers, ChangeKind::Modified, id);
+        notify_tag(&self.tag_subscribers, TagEventKind::Removed, id, tag);
+        true
+    }
+
+    /// Registers a callback for one tag being added to or removed from documents
+    /// Only changes made through the manager are reported: `add_document`,
+    /// `add_tag`, `remove_tag`, `prune_rare_tags`, `bulk_update_metadata` and
+    /// tags changed by processors run from the manager.
+    /// # Arguments
+    /// * `tag` - Tag to listen for
+    /// * `callback` - Function called with each matching tag event
+    pub fn subscribe_to_tag_events(&mut self, tag: &str, callback: Box<dyn Fn(TagEvent)>) {
+        self.tag_subscribers.push((tag.to_string(), callback));
+    }
+
+    /// Registers a callback for document changes made through the manager
+    /// Callbacks run on the thread that made the change, after the lock on
+    /// the watcher list has been released.
+    /// # Arguments
+    /// * `callback` - Function called with each change event
+    /// # Returns
+    /// Handle whose `stop` method deregisters the callback
+    pub fn watch_for_changes<F>(&self, callback: F) -> WatchHandle
+    where
+        F: Fn(ChangeEvent) + Send + Sync + 'static,
+    {
+        let mut watchers = self.watchers.lock().unwrap_or_else(|e| e.into_inner());
+        watchers.next_id += 1;
+        let id = watchers.next_id;
+        watchers.callbacks.push((id, Arc::new(callback)));
+
+        WatchHandle {
+            watchers: Arc::downgrade(&self.watchers),
+            id,
+        }
+    }
+
+    /// Removes a document from the manager
+    /// # Arguments
+    /// * `id` - ID of the document to remove
+    /// # Returns
+    /// The removed document, or None if no document has that ID
+    pub fn remove_document(&mut self, id: &str) -> Option<Document> {
+        let index = self.index_of(id)?;
+        let document = self.documents.remove(index);
+        if let Some(positions) = self.created_index.get_mut(&document.created_at) {
+            positions.retain(|position| *position != index);
+            if positions.is_empty() {
+                self.created_index.remove(&document.created_at);
+            }
+        }
+        // Documents after the removed one have moved down by one
+        for position in self.created_index.values_mut().flatten() {
+            if *position > index {
+                *position -= 1;
+            }
+        }
+        release_author(&mut self.author_counts, &document.metadata.author);
+        self.word_frequency_cache.take();
+        notify_change(&self.watchers, ChangeKind::Removed, id);
+        Some(document)
+    }
+
+    /// Moves a document to the trash so it can be restored later
+    /// Watchers see the document as removed.
+    /// # Arguments
+    /// * `id` - ID of the document to delete
+    /// # Returns
+    /// Ok when deleted, or an error if the document is missing or the trash already holds that ID
+    pub fn soft_delete(&mut self, id: &str) -> Result<(), DeleteError> {
+        if self.deleted.contains_key(id) {
+            return Err(DeleteError::AlreadyDeleted(id.to_string()));
+        }
+        let document = self
+            .remove_document(id)
+            .ok_or_else(|| DeleteError::NotFound(id.to_string()))?;
+
+        self.deleted.insert(id.to_string(), document);
+        Ok(())
+    }
+
+    /// Moves a soft-deleted document back into the manager
+    /// # Arguments
+    /// * `id` - ID of the document to restore
+    /// # Returns
+    /// Ok when restored, or an error if the document is not in the trash or the ID is taken
+    pub fn restore(&mut self, id: &str) -> Result<(), RestoreError> {
+        if self.index_of(id).is_some() {
+            return Err(RestoreError::DuplicateId(id.to_string()));
+        }
+        let document = self
+            .deleted
+            .remove(id)
+            .ok_or_else(|| RestoreError::NotDeleted(id.to_string()))?;
+
+        self.add_document(document);
+        Ok(())
+    }
+
+    /// Permanently discards every soft-deleted document
+    /// # Returns
+    /// Number of documents discarded
+    pub fn purge_deleted(&mut self) -> usize {
+        let purged = self.deleted.len();
+        self.deleted.clear();
+        purged
+    }
+
+    /// Moves a document into another manager
+    /// Both managers are left unchanged if the move cannot complete.
+    /// # Arguments
+    /// * `id` - ID of the document to move
+    /// * `target` - Manager receiving the document
+    /// # Returns
+    /// Ok when moved, or an error if the document is missing or the ID is taken
+    pub fn move_document(&mut self, id: &str, target: &mut DocumentManager) -> Result<(), MoveError> {
+        if target.index_of(id).is_some() {
+            return Err(MoveError::DuplicateId(id.to_string()));
+        }
+        let document = self
+            .remove_document(id)
+            .ok_or_else(|| MoveError::NotFound(id.to_string()))?;
+
+        target.add_document(document);
+        Ok(())
+    }
+
+    /// Builds a new manager from copies of the matching documents
+    /// The new manager starts with no processors, watchers or tag subscribers.
+    /// # Arguments
+    /// * `predicate` - Returns true for documents to copy
+    /// # Returns
+    /// Manager holding the copied documents in their original order
+    pub fn filter_and_clone(&self, predicate: impl Fn(&Document) -> bool) -> DocumentManager {
+        let matching: Vec<&Document> = self.documents.iter().filter(|doc| predicate(doc)).collect();
+        let mut manager = DocumentManager::with_capacity(matching.len());
+        for document in matching {
+            manager.add_document(document.clone());
+        }
+        manager
+    }
+
+    /// Finds documents created after a point in time
+    /// Suited to polling: pass the `created_at` of the last result seen.
+    /// # Arguments
+    /// * `since` - Exclusive lower bound on `created_at`
+    /// # Returns
+    /// Matching documents sorted by `created_at` ascending
+    pub fn find_by_created_since(&self, since: std::time::SystemTime) -> Vec<&Document> {
+        self.created_index
+            .range((std::ops::Bound::Excluded(since), std::ops::Bound::Unbounded))
+            .flat_map(|(_, positions)| positions)
+            .map(|position| &self.documents[*position])
+            .collect()
+    }
+
+    /// Finds documents by author
+    /// # Arguments
+    /// * `author` - Author name to search for
+    /// # Returns
+    /// Vector of matching documents
+    pub fn find_by_author(&self, author: &str) -> Vec<&Document> {
         self.documents
             .iter()
-            .filter(|doc| doc.metadata.tags.contains(&tag.to_string()))
+            .filter(|doc| doc.metadata.author.to_lowercase() == author.to_lowercase())
             .collect()
     }
-}
 
-impl Default for DocumentManager {
-    fn default() -> Self {
-        Self::new()
+    /// Finds soft-deleted documents by author
+    /// # Arguments
+    /// * `author` - Author name to search for
+    /// # Returns
+    /// Vector of matching documents in the trash, sorted by ID
+    pub fn find_deleted_by_author(&self, author: &str) -> Vec<&Document> {
+        let mut matches: Vec<&Document> = self
+            .deleted
+            .values()
+            .filter(|doc| doc.metadata.author.to_lowercase() == author.to_lowercase())
+            .collect();
+        matches.sort_by(|a, b| a.id.cmp(&b.id));
+        matches
+    }
+
+    /// Finds documents by type
+    /// # Arguments
+    /// * `doc_type` - Document type to search for
+    /// # Returns
+    /// Vector of matching documents
+    pub fn find_by_type(&self, doc_type: &DocumentType) -> Vec<&Document> {
+        self.documents
+            .iter()
+            .filter(|doc| &doc.doc_type == doc_type)
+            .collect()
     }
-}
\ No newline at end of file
+
+    /// Finds documents by their latest processing status for one processor
+    /// # Arguments
+    /// * `processor_name` - Processor whose history records are checked
+    /// * `status` - State the latest record must be in
+    /// # Returns
+    /// Vector of matching documents
+    pub fn find_by_processing_status(&self, processor_name: &str, status: ProcessingStatusFilter) -> Vec<&Document> {
+        self.documents
+            .iter()
+            .filter(|doc| {
+                let latest = doc
+                    .metadata
+                    .processing_history
+                    .iter()
+                    .rev()
+                    .find(|record| record.processor_name == processor_name)
+                    .map(|record| &record.status);
+                matches!(
+                    (status, latest),
+                    (ProcessingStatusFilter::Any, _)
+                        | (ProcessingStatusFilter::Completed, Some(ProcessingStatus::Completed))
+                        | (ProcessingStatusFilter::Failed, Some(ProcessingStatus::Failed(_)))
+                        | (ProcessingStatusFilter::Pending, None)
+                        | (ProcessingStatusFilter::Pending, Some(ProcessingStatus::Pending))
+                        | (ProcessingStatusFilter::Pending, Some(ProcessingStatus::Processing))
+                )
+            })
+            .collect()
+    }
+
+    /// Processes all documents using available processors
+    /// Every run is appended to the document's processing history.
+    /// # Returns
+    /// Vector of processing results
+    pub fn process_all_documents(&mut self) -> Vec<Result<ProcessingStatus, String>> {
+        let mut results = Vec::new();
+        
+        for (position, document) in self.documents.iter_mut().enumerate() {
+            let previous_tags = document.metadata.tags.clone();
+            let previous_author = document.metadata.author.clone();
+            let previous_created = document.created_at;
+            for processor in &self.processors {
+                results.push(run_and_record(processor.as_ref(), document));
+            }
+            if !self.processors.is_empty() {
+                self.word_frequency_cache.take();
+                notify_change(&self.watchers, ChangeKind::Modified, &document.id);
+                notify_tag_changes(&self.tag_subscribers, &document.id, &previous_tags, &document.metadata.tags);
+                reassign_author(&mut self.author_counts, &previous_author, &document.metadata.author);
+                move_created(&mut self.created_index, position, previous_created, document.created_at);
+            }
+        }
+        
+        results
+    }
+
+    /// Processes only documents not yet completed by each processor
+    /// Every run is appended to the document's processing history.
+    /// # Returns
+    /// Vector of processing results for the runs that were performed
+    pub fn process_unprocessed_only(&mut self) -> Vec<Result<ProcessingStatus, String>> {
+        let mut results = Vec::new();
+
+        for (position, document) in self.documents.iter_mut().enumerate() {
+            let runs = results.len();
+            let previous_tags = document.metadata.tags.clone();
+            let previous_author = document.metadata.author.clone();
+            let previous_created = document.created_at;
+            for processor in &self.processors {
+                if document.is_processed_by(processor.name()) {
+                    continue;
+                }
+
+                results.push(run_and_record(processor.as_ref(), document));
+            }
+            if results.len() > runs {
+                self.word_frequency_cache.take();
+                notify_change(&self.watchers, ChangeKind::Modified, &document.id);
+                notify_tag_changes(&self.tag_subscribers, &document.id, &previous_tags, &document.metadata.tags);
+                reassign_author(&mut self.author_counts, &previous_author, &document.metadata.author);
+                move_created(&mut self.created_index, position, previous_created, document.created_at);
+            }
+        }
+
+        results
+    }
+
+    /// Reprocesses one document with a specific processor
+    /// Any previous history record from that processor is replaced.
+    /// # Arguments
+    /// * `id` - ID of the document to reprocess
+    /// * `processor_name` - Name of the processor to run
+    /// # Returns
+    /// New processing status, or an error for unknown inputs or a failed run
+    pub fn force_reprocess(&mut self, id: &str, processor_name: &str) -> Result<ProcessingStatus, ProcessError> {
+        let index = self
+            .index_of(id)
+            .ok_or_else(|| ProcessError::DocumentNotFound(id.to_string()))?;
+        let processor = self
+            .processors
+            .iter()
+            .find(|p| p.name() == processor_name)
+            .ok_or_else(|| ProcessError::ProcessorNotFound(processor_name.to_string()))?;
+
+        let document = &mut self.documents[index];
+        document
+            .metadata
+            .processing_history
+            .retain(|record| record.processor_name != processor_name);
+
+        let previous_tags = document.metadata.tags.clone();
+        let previous_author = document.metadata.author.clone();
+        let previous_created = document.created_at;
+        let result = run_and_record(processor.as_ref(), document);
+        self.word_frequency_cache.take();
+        notify_change(&self.watchers, ChangeKind::Modified, id);
+        notify_tag_changes(&self.tag_subscribers, id, &previous_tags, &document.metadata.tags);
+        reassign_author(&mut self.author_counts, &previous_author, &document.metadata.author);
+        move_created(&mut self.created_index, index, previous_created, document.created_at);
+        result.map_err(ProcessError::ProcessingFailed)
+    }
+
+    /// Summarizes the processing history of all documents
+    /// Records that are neither `Completed` nor `Failed` count as skipped.
+    /// # Returns
+    /// Per-processor summary of runs and durations
+    pub fn get_processing_report(&self) -> ProcessingReport {
+        let mut report = ProcessingReport::default();
+
+        for document in &self.documents {
+            for record in &document.metadata.processing_history {
+                let summary = report.by_processor.entry(record.processor_name.clone()).or_default();
+                match record.status {
+                    ProcessingStatus::Completed => summary.completed += 1,
+                    ProcessingStatus::Failed(_) => summary.failed += 1,
+                    ProcessingStatus::Pending | ProcessingStatus::Processing => summary.skipped += 1,
+                }
+                summary.total_duration_ms += record.duration_ms;
+            }
+        }
+
+        report
+    }
+
+    /// Builds a histogram of document word counts
+    /// Only buckets that hold at least one document are listed, so memory
+    /// grows with the number of documents rather than the longest one.
+    /// # Arguments
+    /// * `bucket_size` - Width of each bucket in words
+    /// # Returns
+    /// Pairs of `(bucket_start, document_count)` sorted by bucket start, or an
+    /// error for a zero bucket size
+    pub fn word_count_histogram(&self, bucket_size: usize) -> Result<Vec<(usize, usize)>, String> {
+        if bucket_size == 0 {
+            return Err("Bucket size must be greater than zero".to_string());
+        }
+
+        let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+        for document in &self.documents {
+            *counts.entry(document.metadata.word_count / bucket_size * bucket_size).or_insert(0) += 1;
+        }
+
+        Ok(counts.into_iter().collect())
+    }
+
+    /// Computes text metrics across all documents
+    /// Content is tokenized once; a sentence ends at a word ending in `.`, `!`
+    /// or `?`, and trailing text without a terminator counts as a sentence.
+    /// # Returns
+    /// Totals, ratios are 0.0 for an empty corpus
+    pub fn text_statistics(&self) -> TextStatistics {
+        let mut stats = TextStatistics::default();
+        let mut vocabulary: HashSet<String> = HashSet::new();
+
+        for document in &self.documents {
+            stats.total_characters += document.content.chars().count();
+            let mut in_sentence = false;
+            for token in document.content.split_whitespace() {
+                let word = token.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+                if !word.is_empty() {
+                    stats.total_words += 1;
+                    in_sentence = true;
+                    vocabulary.insert(word);
+                }
+                let ends_sentence = token
+                    .trim_end_matches(['"', '\'', ')', ']'])
+                    .ends_with(['.', '!', '?']);
+                if ends_sentence && in_sentence {
+                    stats.total_sentences += 1;
+                    in_sentence = false;
+                }
+            }
+            if in_sentence {
+                stats.total_sentences += 1;
+            }
+        }
+
+        stats.vocabulary_size = vocabulary.len();
+        if stats.total_sentences > 0 {
+            stats.average_sentence_length = stats.total_words as f64 / stats.total_sentences as f64;
+        }
+        if stats.total_words > 0 {
+            stats.type_token_ratio = stats.vocabulary_size as f64 / stats.total_words as f64;
+        }
+        stats
+    }
+
+    /// Duplicates a document under a new ID
+    /// The copy gets a fresh creation time and an empty processing history.
+    /// # Arguments
+    /// * `id` - ID of the document to copy
+    /// * `new_id` - ID for the copy
+    /// # Returns
+    /// Reference to the inserted copy
+    pub fn copy_document(&mut self, id: &str, new_id: &str) -> Result<&Document, CopyError> {
+        if self.index_of(new_id).is_some() {
+            return Err(CopyError::DestinationExists(new_id.to_string()));
+        }
+        let index = self
+            .index_of(id)
+            .ok_or_else(|| CopyError::SourceNotFound(id.to_string()))?;
+
+        let mut copy = self.documents[index].clone();
+        copy.id = new_id.to_string();
+        copy.created_at = std::time::SystemTime::now();
+        copy.last_modified = copy.created_at;
+        copy.metadata.processing_history.clear();
+        self.add_document(copy);
+
+        Ok(&self.documents[self.documents.len() - 1])
+    }
+
+    /// Computes the Jaccard similarity of two documents' vocabularies
+    /// Words are compared case-insensitively with surrounding punctuation trimmed.
+    /// # Arguments
+    /// * `id1` - ID of the first document
+    /// * `id2` - ID of the second document
+    /// # Returns
+    /// Shared words divided by all distinct words (0.0 when both are empty)
+    pub fn word_overlap(&self, id1: &str, id2: &str) -> Result<f64, OverlapError> {
+        let find = |id: &str| {
+            self.index_of(id)
+                .map(|index| &self.documents[index])
+                .ok_or_else(|| OverlapError::DocumentNotFound(id.to_string()))
+        };
+        let words1 = word_set(&find(id1)?.content);
+        let words2 = word_set(&find(id2)?.content);
+
+        let union = words1.union(&words2).count();
+        if union == 0 {
+            return Ok(0.0);
+        }
+
+        Ok(words1.intersection(&words2).count() as f64 / union as f64)
+    }
+
+    /// Finds registered processors whose average run exceeds a duration
+    /// Averages come from the documents' processing history.
+    /// # Arguments
+    /// * `threshold_ms` - Average duration in milliseconds to exceed
+    /// # Returns
+    /// Names of slow processors in registration order
+    pub fn find_long_running_processors(&self, threshold_ms: u64) -> Vec<String> {
+        let report = self.get_processing_report();
+
+        self.processors
+            .iter()
+            .map(|processor| processor.name())
+            .filter(|name| {
+                report.by_processor.get(*name).is_some_and(|summary| {
+                    // Compare totals so a fractional average is not truncated
+                    let runs = (summary.completed + summary.failed + summary.skipped) as u128;
+                    runs > 0 && u128::from(summary.total_duration_ms) > u128::from(threshold_ms) * runs
+                })
+            })
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Runs the built-in processor for each document type in the corpus
+    /// Text, Html and Markdown documents get `TextProcessor`, `HtmlProcessor`
+    /// and `MarkdownProcessor`; other types are skipped. The processors are
+    /// not registered with the manager, but their runs are recorded in each
+    /// document's processing history.
+    /// # Returns
+    /// Vector of processing results
+    pub fn apply_default_processors(&mut self) -> Vec<Result<ProcessingStatus, String>> {
+        let mut defaults: Vec<Box<dyn DocumentProcessor>> = Vec::new();
+        for document in &self.documents {
+            if defaults.iter().any(|p| p.supports(&document.doc_type)) {
+                continue;
+            }
+            match document.doc_type {
+                DocumentType::Text => defaults.push(Box::new(TextProcessor)),
+                DocumentType::Html => defaults.push(Box::new(HtmlProcessor)),
+                DocumentType::Markdown => defaults.push(Box::new(MarkdownProcessor)),
+                _ => {}
+            }
+        }
+
+        let mut results = Vec::new();
+        for (position, document) in self.documents.iter_mut().enumerate() {
+            let runs = results.len();
+            let previous_tags = document.metadata.tags.clone();
+            let previous_author = document.metadata.author.clone();
+            let previous_created = document.created_at;
+            for processor in &defaults {
+                if processor.supports(&document.doc_type) {
+                    results.push(run_and_record(processor.as_ref(), document));
+                }
+            }
+            if results.len() > runs {
+                self.word_frequency_cache.take();
+                notify_change(&self.watchers, ChangeKind::Modified, &document.id);
+                notify_tag_changes(&self.tag_subscribers, &document.id, &previous_tags, &document.metadata.tags);
+                reassign_author(&mut self.author_counts, &previous_author, &document.metadata.author);
+                move_created(&mut self.created_index, position, previous_created, document.created_at);
+            }
+        }
+
+        results
+    }
+
+    /// Exports the document similarity graph as GraphML
+    /// Documents are nodes; pairs whose word-frequency cosine similarity
+    /// exceeds 0.5 are joined by an undirected edge weighted by that similarity.
+    /// # Returns
+    /// GraphML XML document
+    pub fn to_graph_ml(&self) -> String {
+        let frequencies: Vec<HashMap<String, usize>> =
+            self.documents.iter().map(|doc| word_frequencies(&doc.content)).collect();
+
+        let mut xml = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"title\" for=\"node\" attr.name=\"title\" attr.type=\"string\"/>\n",
+            "  <key id=\"type\" for=\"node\" attr.name=\"type\" attr.type=\"string\"/>\n",
+            "  <key id=\"word_count\" for=\"node\" attr.name=\"word_count\" attr.type=\"int\"/>\n",
+            "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"double\"/>\n",
+            "  <graph id=\"documents\" edgedefault=\"undirected\">\n",
+        ));
+
+        for document in &self.documents {
+            xml.push_str(&format!(
+                "    <node id=\"{}\">\n      <data key=\"title\">{}</data>\n      <data key=\"type\">{:?}</data>\n      <data key=\"word_count\">{}</data>\n    </node>\n",
+                escape_xml(&document.id),
+                escape_xml(&document.title),
+                document.doc_type,
+                document.metadata.word_count
+            ));
+        }
+
+        for (i, a) in self.documents.iter().enumerate() {
+            for (j, b) in self.documents.iter().enumerate().skip(i + 1) {
+                let similarity = cosine_similarity(&frequencies[i], &frequencies[j]);
+                if similarity > 0.5 {
+                    xml.push_str(&format!(
+                        "    <edge source=\"{}\" target=\"{}\">\n      <data key=\"weight\">{:.4}</data>\n    </edge>\n",
+                        escape_xml(&a.id),
+                        escape_xml(&b.id),
+                        similarity
+                    ));
+                }
+            }
+        }
+
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+
+    /// Draws the processor pipeline as an ASCII flow diagram
+    /// Each processor is a box listing its supported types, priority and
+    /// average run time from the processing history. Dispatching processors
+    /// branch into their targets, labelled with the routing condition.
+    /// # Returns
+    /// Multi-line diagram in processor order
+    pub fn pipeline_visualization(&self) -> String {
+        if self.processors.is_empty() {
+            return "(no processors)\n".to_string();
+        }
+
+        let mut durations: HashMap<&str, (u64, u64)> = HashMap::new();
+        for record in self.documents.iter().flat_map(|doc| &doc.metadata.processing_history) {
+            let (total, runs) = durations.entry(&record.processor_name).or_insert((0, 0));
+            *total += record.duration_ms;
+            *runs += 1;
+        }
+
+        let mut lines = Vec::new();
+        for (index, processor) in self.processors.iter().enumerate() {
+            if index > 0 {
+                lines.push("      |".to_string());
+                lines.push("      v".to_string());
+            }
+            draw_processor(processor.as_ref(), &durations, "", &mut lines);
+        }
+
+        let mut diagram = lines.join("\n");
+        diagram.push('\n');
+        diagram
+    }
+
+    /// Finds the most frequent words across all documents
+    /// Corpus frequencies are cached until a document is added, removed or
+    /// modified through the manager.
+    /// # Arguments
+    /// * `n` - Maximum number of words to return
+    /// * `exclude_stopwords` - Skip words listed in `STOPWORDS`
+    /// # Returns
+    /// `(word, count)` sorted by count descending, then alphabetically
+    pub fn get_most_common_words(&self, n: usize, exclude_stopwords: bool) -> Vec<(String, usize)> {
+        let mut cache = self.word_frequency_cache.borrow_mut();
+        let frequencies = cache.get_or_insert_with(|| {
+            let mut corpus: HashMap<String, usize> = HashMap::new();
+            for document in &self.documents {
+                for (word, count) in document.word_frequency_map() {
+                    *corpus.entry(word).or_insert(0) += count;
+                }
+            }
+            corpus
+        });
+
+        let mut words: Vec<(String, usize)> = frequencies
+            .iter()
+            .filter(|(word, _)| !exclude_stopwords || !STOPWORDS.contains(&word.as_str()))
+            .map(|(word, count)| (word.clone(), *count))
+            .collect();
+        words.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        words.truncate(n);
+        words
+    }
+
+    /// Removes tags used by fewer than a minimum number of documents
+    /// # Arguments
+    /// * `min_document_count` - Documents a tag must appear on to be kept (0 keeps all)
+    /// # Returns
+    /// Number of tag instances removed
+    pub fn prune_rare_tags(&mut self, min_document_count: usize) -> usize {
+        if min_document_count == 0 {
+            return 0;
+        }
+
+        let mut document_counts: HashMap<String, usize> = HashMap::new();
+        for document in &self.documents {
+            for tag in &document.metadata.tags {
+                *document_counts.entry(tag.clone()).or_insert(0) += 1;
+            }
+        }
+
+        let mut removed = 0;
+        for document in &mut self.documents {
+            let rare: Vec<String> = document
+                .metadata
+                .tags
+                .iter()
+                .filter(|tag| document_counts[*tag] < min_document_count)
+                .cloned()
+                .collect();
+            for tag in &rare {
+                if document.remove_tag(tag) {
+                    removed += 1;
+                    notify_tag(&self.tag_subscribers, TagEventKind::Removed, &document.id, tag);
+                }
+            }
+            if !rare.is_empty() {
+                self.word_frequency_cache.take();
+                notify_change(&self.watchers, ChangeKind::Modified, &document.id);
+            }
+        }
+
+        removed
+    }
+
+    /// Applies a metadata update to every document
+    /// Only documents whose metadata actually changed get a new modification
+    /// time and are reported to watchers as modified.
+    /// # Arguments
+    /// * `f` - Function called with the document and a mutable copy of its metadata
+    pub fn bulk_update_metadata<F>(&mut self, f: F)
+    where
+        F: Fn(&Document, &mut DocumentMetadata),
+    {
+        for document in &mut self.documents {
+            let mut metadata = document.metadata.clone();
+            f(document, &mut metadata);
+            if metadata == document.metadata {
+                continue;
+            }
+            let previous = std::mem::replace(&mut document.metadata, metadata);
+            document.touch();
+            reassign_author(&mut self.author_counts, &previous.author, &document.metadata.author);
+            self.word_frequency_cache.take();
+            notify_change(&self.watchers, ChangeKind::Modified, &document.id);
+            notify_tag_changes(&self.tag_subscribers, &document.id, &previous.tags, &document.metadata.tags);
+        }
+    }
+
+    /// Gets total number of documents
+    /// # Returns
+    /// Document count
+    pub fn document_count(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Lists the authors of the managed documents
+    /// # Returns
+    /// Sorted author names without duplicates
+    pub fn all_authors(&self) -> Vec<String> {
+        self.author_counts.keys().cloned().collect()
+    }
+
+    /// Counts the distinct authors of the managed documents
+    /// # Returns
+    /// Number of distinct author names
+    pub fn unique_author_count(&self) -> usize {
+        self.author_counts.len()
+    }
+
+    /// Gets documents with specific tag
+    /// # Arguments
+    /// * `tag` - Tag to search for
+    /// # Returns
+    /// Vector of documents with the tag
+    pub fn find_by_tag(&self, tag: &str) -> Vec<&Document> {
+        self.documents
+            .iter()
+            .filter(|doc| doc.metadata.tags.contains(&tag.to_string()))
+            .collect()
+    }
+
+    /// Counts how many documents carry each pair of tags
+    /// # Returns
+    /// Map from `(tag_a, tag_b)` with `tag_a < tag_b` to the number of shared documents
+    pub fn tag_co_occurrence(&self) -> HashMap<(String, String), usize> {
+        let mut matrix = HashMap::new();
+
+        for document in &self.documents {
+            let mut tags: Vec<&String> = document.metadata.tags.iter().collect();
+            tags.sort();
+            tags.dedup();
+            for (i, tag_a) in tags.iter().enumerate() {
+                for tag_b in &tags[i + 1..] {
+                    *matrix.entry(((*tag_a).clone(), (*tag_b).clone())).or_insert(0) += 1;
+                }
+            }
+        }
+
+        matrix
+    }
+
+    /// Finds tag pairs that often appear on the same documents
+    /// # Arguments
+    /// * `min_cooccurrence` - Minimum number of shared documents
+    /// # Returns
+    /// `(tag_a, tag_b, count)` sorted by count descending, then by tag names
+    pub fn find_frequently_co_occurring_tags(&self, min_cooccurrence: usize) -> Vec<(String, String, usize)> {
+        let mut pairs: Vec<(String, String, usize)> = self
+            .tag_co_occurrence()
+            .into_iter()
+            .filter(|(_, count)| *count >= min_cooccurrence)
+            .map(|((tag_a, tag_b), count)| (tag_a, tag_b, count))
+            .collect();
+
+        pairs.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| (&a.0, &a.1).cmp(&(&b.0, &b.1))));
+        pairs
+    }
+
+    /// Finds documents changed since their last processing run
+    /// Documents that were never processed always qualify.
+    /// # Returns
+    /// Documents whose `last_modified` is newer than their latest history record
+    pub fn find_documents_needing_reprocessing(&self) -> Vec<&Document> {
+        self.documents
+            .iter()
+            .filter(|doc| {
+                let last_run = doc
+                    .metadata
+                    .processing_history
+                    .last()
+                    .map(|record| record.timestamp)
+                    .unwrap_or(std::time::UNIX_EPOCH);
+                doc.last_modified > last_run
+            })
+            .collect()
+    }
+
+    /// Orders documents so referenced documents come before those referencing them
+    /// Documents sharing an ID form a single node and are emitted together.
+    /// # Returns
+    /// Documents in topological order, or the IDs on a reference cycle
+    pub fn topological_sort_by_references(&self) -> Result<Vec<&Document>, CycleError> {
+        let graph = DocumentGraph::build(self);
+        let mut ids: Vec<&str> = Vec::new();
+        let mut documents_by_id: HashMap<&str, Vec<&Document>> = HashMap::new();
+        for document in &self.documents {
+            let same_id = documents_by_id.entry(&document.id).or_default();
+            if same_id.is_empty() {
+                ids.push(&document.id);
+            }
+            same_id.push(document);
+        }
+
+        let mut pending: HashMap<&str, usize> = HashMap::new();
+        let mut referenced_by: HashMap<&str, Vec<&str>> = HashMap::new();
+        for &id in &ids {
+            let references = graph.references_of(id);
+            pending.insert(id, references.len());
+            for referenced in references {
+                referenced_by.entry(referenced.as_str()).or_default().push(id);
+            }
+        }
+
+        let mut ready: VecDeque<&str> = ids.iter().copied().filter(|id| pending[id] == 0).collect();
+        let mut sorted = Vec::with_capacity(self.documents.len());
+        while let Some(id) = ready.pop_front() {
+            sorted.extend(documents_by_id[id].iter().copied());
+            for &referencing in referenced_by.get(id).into_iter().flatten() {
+                let count = pending.get_mut(referencing).expect("graph ids come from the manager");
+                *count -= 1;
+                if *count == 0 {
+                    ready.push_back(referencing);
+                }
+            }
+        }
+
+        if sorted.len() == self.documents.len() {
+            return Ok(sorted);
+        }
+
+        // Every unsorted document still references another unsorted one, so
+        // following those references from any of them must loop.
+        let mut path: Vec<&str> = Vec::new();
+        let mut current = ids
+            .iter()
+            .copied()
+            .find(|id| pending[id] > 0)
+            .expect("an unsorted document remains");
+        while !path.contains(&current) {
+            path.push(current);
+            current = graph
+                .references_of(current)
+                .iter()
+                .map(String::as_str)
+                .find(|referenced| pending[referenced] > 0)
+                .expect("unsorted documents reference another unsorted document");
+        }
+        let start = path.iter().position(|id| *id == current).unwrap_or(0);
+
+        Err(CycleError {
+            document_ids: path[start..].iter().map(|id| id.to_string()).collect(),
+        })
+    }
+
+    /// Builds the collaboration graph between document authors
+    /// # Returns
+    /// Author graph weighted by shared tags
+    pub fn compute_author_graph(&self) -> AuthorGraph {
+        AuthorGraph::build(self)
+    }
+
+    /// Finds the position of a document by ID
+    fn index_of(&self, id: &str) -> Option<usize> {
+        self.documents.iter().position(|doc| doc.id == id)
+    }
+
+    /// Finds documents that no registered processor supports
+    /// # Returns
+    /// Vector of documents that would be left unprocessed
+    pub fn find_orphaned_documents(&self) -> Vec<&Document> {
+        self.documents
+            .iter()
+            .filter(|doc| self.processors.iter().all(|p| !p.supports(&doc.doc_type)))
+            .collect()
+    }
+}
+
+/// Derives the stable pseudonym used for an author name
+fn author_pseudonym(author: &str) -> String {
+    let digest = sha256(author.as_bytes());
+    let prefix: String = digest[..4].iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("Author_{}", prefix)
+}
+
+/// Computes the SHA-256 digest of a byte string
+fn sha256(data: &[u8]) -> [u8; 32] {
+    const K: [u32; 64] = [
+        0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+        0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+        0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+        0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+        0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+        0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+        0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+        0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+    ];
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+    ];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let temp1 = h.wrapping_add(s1).wrapping_add(choice).wrapping_add(K[i]).wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let temp2 = s0.wrapping_add(majority);
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = temp1.wrapping_add(temp2);
+        }
+
+        for (value, added) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *value = value.wrapping_add(added);
+        }
+    }
+
+    let mut digest = [0u8; 32];
+    for (chunk, value) in digest.chunks_mut(4).zip(state) {
+        chunk.copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
+
+/// Appends the box of one processor, and of the processors it dispatches to, to a diagram
+fn draw_processor(
+    processor: &dyn DocumentProcessor,
+    durations: &HashMap<&str, (u64, u64)>,
+    indent: &str,
+    lines: &mut Vec<String>,
+) {
+    const ALL_TYPES: [DocumentType; 9] = [
+        DocumentType::Text,
+        DocumentType::Markdown,
+        DocumentType::Html,
+        DocumentType::Pdf,
+        DocumentType::Word,
+        DocumentType::Json,
+        DocumentType::Code(CodeLanguage::Rust),
+        DocumentType::Code(CodeLanguage::Python),
+        DocumentType::Code(CodeLanguage::JavaScript),
+    ];
+
+    let supported: Vec<String> = ALL_TYPES
+        .iter()
+        .filter(|doc_type| processor.supports(doc_type))
+        .map(|doc_type| format!("{:?}", doc_type))
+        .collect();
+    let types = if supported.len() == ALL_TYPES.len() {
+        "all".to_string()
+    } else if supported.is_empty() {
+        "none".to_string()
+    } else {
+        supported.join(", ")
+    };
+    let timing = match durations.get(processor.name()) {
+        Some((total, 1)) => format!("{} ms over 1 run", total),
+        Some((total, runs)) => format!("{} ms avg over {} runs", total / runs, runs),
+        None => "no runs".to_string(),
+    };
+
+    let rows = [
+        processor.name().to_string(),
+        format!("types: {}", types),
+        format!("priority: {}", processor.priority()),
+        format!("time: {}", timing),
+    ];
+    let width = rows.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+    let border = format!("{}+{}+", indent, "-".repeat(width + 2));
+    lines.push(border.clone());
+    for row in &rows {
+        lines.push(format!("{}| {:<width$} |", indent, row, width = width));
+    }
+    lines.push(border);
+
+    let branches = processor.branches();
+    for (index, (condition, target)) in branches.iter().enumerate() {
+        let last = index + 1 == branches.len();
+        lines.push(format!("{}  {}-- {}", indent, if last { '`' } else { '|' }, condition));
+        let child_indent = format!("{}  {}     ", indent, if last { ' ' } else { '|' });
+        draw_processor(*target, durations, &child_indent, lines);
+    }
+}
+
+/// Counts the backticks at the start of a string
+fn backtick_run(text: &str) -> usize {
+    text.bytes().take_while(|byte| *byte == b'`').count()
+}
+
+/// Parses an APA-style `Author, 2020` citation into `Author 2020`
+fn parse_author_year(citation: &str) -> Option<String> {
+    let (authors, year) = citation.trim().rsplit_once(',')?;
+    let authors = authors.trim();
+    let year = year.trim();
+
+    let digits = year.trim_end_matches(|c: char| c.is_ascii_lowercase());
+    let is_year = digits.len() == 4 && digits.chars().all(|c| c.is_ascii_digit()) && year.len() <= 5;
+    let is_name = authors.starts_with(|c: char| c.is_uppercase());
+
+    (is_year && is_name).then(|| format!("{} {}", authors, year))
+}
+
+/// Escapes a value for use inside a Markdown table cell
+fn escape_table_cell(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// Splits text into lowercase words with surrounding punctuation trimmed
+fn normalized_words(content: &str) -> impl Iterator<Item = String> + '_ {
+    content
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .filter(|word| !word.is_empty())
+}
+
+/// Collects the distinct normalized words of a text
+fn word_set(content: &str) -> HashSet<String> {
+    normalized_words(content).collect()
+}
+
+/// Calls every registered change callback
+/// The callbacks are copied out first so they may register or stop watchers.
+fn notify_change(watchers: &Mutex<ChangeWatchers>, kind: ChangeKind, document_id: &str) {
+    let callbacks: Vec<ChangeCallback> = watchers
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .callbacks
+        .iter()
+        .map(|(_, callback)| callback.clone())
+        .collect();
+
+    for callback in callbacks {
+        callback(ChangeEvent {
+            kind,
+            document_id: document_id.to_string(),
+            timestamp: std::time::SystemTime::now(),
+        });
+    }
+}
+
+/// Drops one document from an author's count, forgetting authors with none left
+fn release_author(author_counts: &mut BTreeMap<String, usize>, author: &str) {
+    if let Some(count) = author_counts.get_mut(author) {
+        *count -= 1;
+        if *count == 0 {
+            author_counts.remove(author);
+        }
+    }
+}
+
+/// Moves one document's count from its previous author to its current one
+fn reassign_author(author_counts: &mut BTreeMap<String, usize>, previous: &str, current: &str) {
+    if previous != current {
+        release_author(author_counts, previous);
+        *author_counts.entry(current.to_string()).or_insert(0) += 1;
+    }
+}
+
+/// Moves a document position to another creation time in the creation index
+fn move_created(
+    created_index: &mut BTreeMap<std::time::SystemTime, Vec<usize>>,
+    position: usize,
+    previous: std::time::SystemTime,
+    current: std::time::SystemTime,
+) {
+    if previous == current {
+        return;
+    }
+    if let Some(positions) = created_index.get_mut(&previous) {
+        positions.retain(|other| *other != position);
+        if positions.is_empty() {
+            created_index.remove(&previous);
+        }
+    }
+    created_index.entry(current).or_default().push(position);
+}
+
+/// Calls the tag subscribers registered for a tag
+fn notify_tag(subscribers: &[(String, TagCallback)], kind: TagEventKind, document_id: &str, tag: &str) {
+    for (_, callback) in subscribers.iter().filter(|(subscribed, _)| subscribed == tag) {
+        callback(TagEvent {
+            kind,
+            document_id: document_id.to_string(),
+            tag: tag.to_string(),
+        });
+    }
+}
+
+/// Notifies tag subscribers of every tag removed from or added to a document
+fn notify_tag_changes(subscribers: &[(String, TagCallback)], document_id: &str, before: &[String], after: &[String]) {
+    for tag in before.iter().filter(|tag| !after.contains(tag)) {
+        notify_tag(subscribers, TagEventKind::Removed, document_id, tag);
+    }
+    for tag in after.iter().filter(|tag| !before.contains(tag)) {
+        notify_tag(subscribers, TagEventKind::Added, document_id, tag);
+    }
+}
+
+/// Checks whether a text mentions an ID as a whole token
+/// Letters, digits, `-` and `_` around a match make it part of a longer token.
+fn contains_id(text: &str, id: &str) -> bool {
+    let is_token_char = |c: char| c.is_alphanumeric() || matches!(c, '-' | '_');
+    !id.is_empty()
+        && text.match_indices(id).any(|(start, _)| {
+            let before = text[..start].chars().next_back();
+            let after = text[start + id.len()..].chars().next();
+            !before.is_some_and(is_token_char) && !after.is_some_and(is_token_char)
+        })
+}
+
+/// Counts occurrences of each normalized word in a text
+fn word_frequencies(content: &str) -> HashMap<String, usize> {
+    let mut frequencies = HashMap::new();
+    for word in normalized_words(content) {
+        *frequencies.entry(word).or_insert(0) += 1;
+    }
+    frequencies
+}
+
+/// Computes the cosine similarity of two word-frequency vectors
+fn cosine_similarity(a: &HashMap<String, usize>, b: &HashMap<String, usize>) -> f64 {
+    let dot: f64 = a
+        .iter()
+        .filter_map(|(word, count)| b.get(word).map(|other| (*count * *other) as f64))
+        .sum();
+    let norm = |v: &HashMap<String, usize>| v.values().map(|c| (*c * *c) as f64).sum::<f64>().sqrt();
+
+    let denominator = norm(a) * norm(b);
+    if denominator == 0.0 {
+        0.0
+    } else {
+        dot / denominator
+    }
+}
+
+/// Escapes text for XML element content and attribute values
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Runs a processor on a document and appends the run to its history
+fn run_and_record(
+    processor: &dyn DocumentProcessor,
+    document: &mut Document,
+) -> Result<ProcessingStatus, String> {
+    let started = std::time::Instant::now();
+    let result = processor.process(document);
+    let status = match &result {
+        Ok(status) => status.clone(),
+        Err(message) => ProcessingStatus::Failed(message.clone()),
+    };
+
+    document.metadata.processing_history.push(ProcessingRecord {
+        processor_name: processor.name().to_string(),
+        status,
+        timestamp: std::time::SystemTime::now(),
+        duration_ms: started.elapsed().as_millis() as u64,
+    });
+
+    result
+}
+
+impl Default for DocumentManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// MinHash index for finding near-duplicate documents
+/// Each document's word set gets a 128-value MinHash signature, split into
+/// 32 bands of 4 rows for locality sensitive hashing. Only documents sharing
+/// at least one band bucket are compared.
+pub struct DocumentFingerprintIndex {
+    ids: Vec<String>,
+    signatures: Vec<Vec<u64>>,
+    buckets: HashMap<(usize, u64), Vec<usize>>,
+}
+
+impl DocumentFingerprintIndex {
+    const PERMUTATIONS: usize = 128;
+    const ROWS_PER_BAND: usize = 4;
+
+    /// Builds the index over every non-empty document of a manager
+    /// # Arguments
+    /// * `manager` - Documents to index
+    pub fn build(manager: &DocumentManager) -> Self {
+        let mut index = DocumentFingerprintIndex {
+            ids: Vec::new(),
+            signatures: Vec::new(),
+            buckets: HashMap::new(),
+        };
+
+        for document in &manager.documents {
+            let words = word_set(&document.content);
+            if words.is_empty() {
+                continue;
+            }
+
+            let signature: Vec<u64> = (0..Self::PERMUTATIONS)
+                .map(|seed| words.iter().map(|word| seeded_hash(seed, word)).min().unwrap_or(u64::MAX))
+                .collect();
+
+            let position = index.ids.len();
+            for (band, rows) in signature.chunks(Self::ROWS_PER_BAND).enumerate() {
+                index.buckets.entry((band, seeded_hash(band, rows))).or_default().push(position);
+            }
+            index.ids.push(document.id.clone());
+            index.signatures.push(signature);
+        }
+
+        index
+    }
+
+    /// Finds document pairs whose estimated Jaccard similarity reaches a threshold
+    /// # Arguments
+    /// * `threshold` - Minimum estimated similarity in `[0.0, 1.0]`
+    /// # Returns
+    /// `(id_a, id_b, similarity)` sorted by similarity descending
+    pub fn find_near_duplicates(&self, threshold: f64) -> Vec<(String, String, f64)> {
+        let mut candidates = HashSet::new();
+        for positions in self.buckets.values() {
+            for (i, &a) in positions.iter().enumerate() {
+                for &b in &positions[i + 1..] {
+                    candidates.insert((a.min(b), a.max(b)));
+                }
+            }
+        }
+
+        let mut pairs: Vec<(String, String, f64)> = candidates
+            .into_iter()
+            .filter_map(|(a, b)| {
+                let matching = self.signatures[a]
+                    .iter()
+                    .zip(&self.signatures[b])
+                    .filter(|(x, y)| x == y)
+                    .count();
+                let similarity = matching as f64 / Self::PERMUTATIONS as f64;
+                (similarity >= threshold).then(|| (self.ids[a].clone(), self.ids[b].clone(), similarity))
+            })
+            .collect();
+
+        pairs.sort_by(|a, b| b.2.total_cmp(&a.2).then_with(|| (&a.0, &a.1).cmp(&(&b.0, &b.1))));
+        pairs
+    }
+}
+
+/// References between the documents of a manager
+/// A document references another when its content contains the other's ID
+/// as a whole token, so `doc-1` is not found inside `doc-10`. Documents
+/// sharing an ID form a single node that references the union of their targets.
+#[derive(Debug, Clone, Default)]
+pub struct DocumentGraph {
+    pub references: HashMap<String, Vec<String>>,
+}
+
+impl DocumentGraph {
+    /// Builds the reference graph of every document in a manager
+    /// # Arguments
+    /// * `manager` - Documents to link
+    pub fn build(manager: &DocumentManager) -> Self {
+        let mut references: HashMap<String, Vec<String>> = HashMap::new();
+        for document in &manager.documents {
+            let targets = references.entry(document.id.clone()).or_default();
+            for other in &manager.documents {
+                if other.id != document.id && !targets.contains(&other.id) && contains_id(&document.content, &other.id) {
+                    targets.push(other.id.clone());
+                }
+            }
+        }
+
+        DocumentGraph { references }
+    }
+
+    /// Gets the IDs a document references
+    /// # Arguments
+    /// * `id` - Referencing document
+    /// # Returns
+    /// Referenced IDs, empty for unknown documents
+    pub fn references_of(&self, id: &str) -> &[String] {
+        self.references.get(id).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+/// Collaboration graph between the authors of a manager's documents
+/// Two authors are connected when their documents share tags; the edge
+/// weight is the number of distinct tags both have used.
+#[derive(Debug, Clone, Default)]
+pub struct AuthorGraph {
+    /// Every author, sorted by name
+    pub authors: Vec<String>,
+    /// Edge weights keyed by `(author_a, author_b)` with `author_a < author_b`
+    pub edges: HashMap<(String, String), u32>,
+}
+
+impl AuthorGraph {
+    /// Builds the author graph of every document in a manager
+    /// # Arguments
+    /// * `manager` - Documents whose authors and tags are linked
+    pub fn build(manager: &DocumentManager) -> Self {
+        let mut tags_by_author: BTreeMap<&str, HashSet<&str>> = BTreeMap::new();
+        for document in &manager.documents {
+            tags_by_author
+                .entry(&document.metadata.author)
+                .or_default()
+                .extend(document.metadata.tags.iter().map(String::as_str));
+        }
+
+        let authors: Vec<(&str, HashSet<&str>)> = tags_by_author.into_iter().collect();
+        let mut edges = HashMap::new();
+        for (i, (author_a, tags_a)) in authors.iter().enumerate() {
+            for (author_b, tags_b) in &authors[i + 1..] {
+                let shared = tags_a.intersection(tags_b).count() as u32;
+                if shared > 0 {
+                    edges.insert((author_a.to_string(), author_b.to_string()), shared);
+                }
+            }
+        }
+
+        AuthorGraph {
+            authors: authors.into_iter().map(|(author, _)| author.to_string()).collect(),
+            edges,
+        }
+    }
+
+    /// Finds the author with the most collaborators
+    /// Ties go to the alphabetically first author.
+    /// # Returns
+    /// `(author, degree)`, or None if no two authors share a tag
+    pub fn most_connected_author(&self) -> Option<(&str, u32)> {
+        let mut degrees: BTreeMap<&str, u32> = BTreeMap::new();
+        for (author_a, author_b) in self.edges.keys() {
+            *degrees.entry(author_a).or_insert(0) += 1;
+            *degrees.entry(author_b).or_insert(0) += 1;
+        }
+
+        degrees
+            .into_iter()
+            .fold(None, |best: Option<(&str, u32)>, (author, degree)| match best {
+                Some((_, best_degree)) if best_degree >= degree => best,
+                _ => Some((author, degree)),
+            })
+    }
+}
+
+/// Hashes a value together with a seed to simulate independent hash functions
+fn seeded_hash<T: Hash + ?Sized>(seed: usize, value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Edges followed by `CallGraph::neighborhood`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CallDirection {
+    Callers,
+    Callees,
+    Both,
+}
+
+/// Caller to callee relationships between functions of one source file
+/// Callees may be externals such as `thread::sleep` or `println!`, which only
+/// appear as callees.
+#[derive(Debug, Clone, Default)]
+pub struct CallGraph {
+    pub adjacency: HashMap<String, Vec<String>>,
+}
+
+impl CallGraph {
+    /// Finds every function transitively called from a function
+    /// # Arguments
+    /// * `fn_name` - Function to start from
+    /// # Returns
+    /// Names reachable through one or more calls (includes `fn_name` only if recursive)
+    pub fn reachable_from(&self, fn_name: &str) -> HashSet<String> {
+        let mut reachable = HashSet::new();
+        let mut queue: VecDeque<&str> = VecDeque::from([fn_name]);
+
+        while let Some(current) = queue.pop_front() {
+            for callee in self.adjacency.get(current).into_iter().flatten() {
+                if reachable.insert(callee.clone()) {
+                    queue.push_back(callee);
+                }
+            }
+        }
+
+        reachable
+    }
+
+    /// Extracts the part of the graph within a number of calls of a function
+    /// # Arguments
+    /// * `fn_name` - Function at the center of the neighborhood
+    /// * `depth` - Maximum number of calls between `fn_name` and a kept function
+    /// * `direction` - Whether to follow calls made by functions, calls made to them, or both
+    /// # Returns
+    /// Graph of the kept functions and every call between them, empty for unknown functions
+    pub fn neighborhood(&self, fn_name: &str, depth: usize, direction: CallDirection) -> CallGraph {
+        let mut callers: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (caller, callees) in &self.adjacency {
+            for callee in callees {
+                callers.entry(callee).or_default().push(caller);
+            }
+        }
+        if !self.adjacency.contains_key(fn_name) && !callers.contains_key(fn_name) {
+            return CallGraph::default();
+        }
+
+        let mut kept: HashSet<&str> = HashSet::from([fn_name]);
+        let mut frontier = vec![fn_name];
+        for _ in 0..depth {
+            let mut next = Vec::new();
+            for current in frontier {
+                let forward = self.adjacency.get(current).into_iter().flatten().map(String::as_str);
+                let backward = callers.get(current).into_iter().flatten().copied();
+                let neighbors: Vec<&str> = match direction {
+                    CallDirection::Callees => forward.collect(),
+                    CallDirection::Callers => backward.collect(),
+                    CallDirection::Both => forward.chain(backward).collect(),
+                };
+                for neighbor in neighbors {
+                    if kept.insert(neighbor) {
+                        next.push(neighbor);
+                    }
+                }
+            }
+            frontier = next;
+        }
+
+        let adjacency = kept
+            .iter()
+            .map(|name| {
+                let callees = self
+                    .adjacency
+                    .get(*name)
+                    .into_iter()
+                    .flatten()
+                    .filter(|callee| kept.contains(callee.as_str()))
+                    .cloned()
+                    .collect();
+                (name.to_string(), callees)
+            })
+            .collect();
+        CallGraph { adjacency }
+    }
+
+    /// Exports the graph in Graphviz DOT format
+    /// # Returns
+    /// `digraph` with one node per function and one edge per call, sorted by name
+    pub fn to_dot(&self) -> String {
+        let quote = |name: &str| format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut callers: Vec<&String> = self.adjacency.keys().collect();
+        callers.sort();
+
+        let mut dot = String::from("digraph calls {\n");
+        for caller in &callers {
+            dot.push_str(&format!("    {};\n", quote(caller)));
+        }
+        for caller in &callers {
+            let mut callees: Vec<&String> = self.adjacency[*caller].iter().collect();
+            callees.sort();
+            for callee in callees {
+                dot.push_str(&format!("    {} -> {};\n", quote(caller), quote(callee)));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Numeric literal found by `CodeAnalyzer::find_magic_numbers`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MagicNumber {
+    /// Literal as written, including a leading minus sign
+    pub value: String,
+    /// 1-based line number
+    pub line: usize,
+    /// Trimmed source line containing the literal
+    pub context: String,
+}
+
+/// Halstead counts returned by `CodeAnalyzer::halstead_metrics`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HalsteadMetrics {
+    pub distinct_operators: usize,
+    pub distinct_operands: usize,
+    pub total_operators: usize,
+    pub total_operands: usize,
+    /// `N * log2(n)` for program length `N` and vocabulary `n`
+    pub volume: f64,
+}
+
+/// Comment metrics returned by `CodeAnalyzer::measure_comment_quality`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommentQualityReport {
+    /// 1-based lines of comments that only restate the following line of code
+    pub redundant_comments: Vec<usize>,
+    pub todo_count: usize,
+    /// Block comments, or runs of line comments, longer than 10 lines
+    pub long_comment_blocks: usize,
+    /// Comment lines per line of code, 0.0 when there is no code
+    pub comment_to_code_ratio: f64,
+}
+
+/// Source code analysis over code documents
+pub struct CodeAnalyzer;
+
+impl CodeAnalyzer {
+    /// Builds the call graph of a Rust source document
+    /// Calls are `identifier(` patterns inside function bodies; string
+    /// literals and comments are ignored. Macro invocations are recorded with
+    /// their `!`, e.g. `println!`, and are never callers themselves. Method
+    /// calls (`.name(`) and `Self::name(` calls only count when made to a
+    /// function defined in the document; other path calls are recorded with
+    /// their last qualifier, e.g. `HashMap::new`, so they are never mistaken
+    /// for a local function of the same name. Capitalized names such as `Ok(`
+    /// or `Some(` are constructors rather than calls.
+    /// # Arguments
+    /// * `doc` - Document to analyze
+    /// # Returns
+    /// Call graph, empty for documents that are not Rust code
+    pub fn function_call_graph(doc: &Document) -> CallGraph {
+        const KEYWORDS: [&str; 11] = ["if", "while", "for", "match", "return", "loop", "in", "as", "fn", "move", "let"];
+
+        let mut graph = CallGraph::default();
+        if doc.doc_type != DocumentType::Code(CodeLanguage::Rust) {
+            return graph;
+        }
+
+        let code: Vec<char> = mask_rust_literals(&doc.content).chars().collect();
+        let mut depth = 0usize;
+        let mut pending: Option<String> = None;
+        let mut open_functions: Vec<(String, usize)> = Vec::new();
+        // `self.name(` calls, kept once every function in the document is known
+        let mut self_calls: Vec<(String, String)> = Vec::new();
+        let mut i = 0;
+
+        while i < code.len() {
+            let c = code[i];
+            if c.is_alphabetic() || c == '_' {
+                let start = i;
+                while i < code.len() && (code[i].is_alphanumeric() || code[i] == '_') {
+                    i += 1;
+                }
+                let word: String = code[start..i].iter().collect();
+                let mut next = i;
+                while next < code.len() && code[next].is_whitespace() {
+                    next += 1;
+                }
+
+                if word == "fn" {
+                    let name_start = next;
+                    let mut name_end = next;
+                    while name_end < code.len() && (code[name_end].is_alphanumeric() || code[name_end] == '_') {
+                        name_end += 1;
+                    }
+                    let name: String = code[name_start..name_end].iter().collect();
+                    if !name.is_empty() {
+                        graph.adjacency.entry(name.clone()).or_default();
+                        pending = Some(name);
+                    }
+                    i = name_end;
+                } else if next < code.len() && code[next] == '!' {
+                    let mut open = next + 1;
+                    while open < code.len() && code[open].is_whitespace() {
+                        open += 1;
+                    }
+                    let invoked = open < code.len() && matches!(code[open], '(' | '[' | '{');
+                    if let (true, None, Some((caller, _))) = (invoked, &pending, open_functions.last()) {
+                        let callees = graph.adjacency.entry(caller.clone()).or_default();
+                        let callee = format!("{}!", word);
+                        if !callees.contains(&callee) {
+                            callees.push(callee);
+                        }
+                    }
+                } else if next < code.len()
+                    && code[next] == '('
+                    && !KEYWORDS.contains(&word.as_str())
+                    && !word.starts_with(char::is_uppercase)
+                {
+                    // Parenthesized words in a pending signature are types, not calls
+                    if let (None, Some((caller, _))) = (&pending, open_functions.last()) {
+                        let callee = match call_qualifier(&code[..start]) {
+                            Some((".", receiver)) if receiver == "self" => {
+                                self_calls.push((caller.clone(), word));
+                                continue;
+                            }
+                            Some(("::", path)) if path == "Self" => {
+                                self_calls.push((caller.clone(), word));
+                                continue;
+                            }
+                            Some((".", _)) => continue,
+                            Some((_, path)) if !path.is_empty() => format!("{}::{}", path, word),
+                            _ => word,
+                        };
+                        let callees = graph.adjacency.entry(caller.clone()).or_default();
+                        if !callees.contains(&callee) {
+                            callees.push(callee);
+                        }
+                    }
+                }
+                continue;
+            }
+
+            match c {
+                '{' => {
+                    depth += 1;
+                    if let Some(name) = pending.take() {
+                        open_functions.push((name, depth));
+                    }
+                }
+                '}' => {
+                    if open_functions.last().is_some_and(|(_, open_depth)| *open_depth == depth) {
+                        open_functions.pop();
+                    }
+                    depth = depth.saturating_sub(1);
+                }
+                ';' => pending = None,
+                _ => {}
+            }
+            i += 1;
+        }
+
+        for (caller, callee) in self_calls {
+            if graph.adjacency.contains_key(&callee) {
+                let callees = graph.adjacency.entry(caller).or_default();
+                if !callees.contains(&callee) {
+                    callees.push(callee);
+                }
+            }
+        }
+
+        graph
+    }
+
+    /// Finds unexplained numeric literals in a Rust source document
+    /// The values 0, 1 and -1 are allowed anywhere, and literals inside
+    /// `const`/`static` items and attributes are skipped.
+    /// # Arguments
+    /// * `doc` - Document to analyze
+    /// # Returns
+    /// Literals in source order, empty for documents that are not Rust code
+    pub fn find_magic_numbers(doc: &Document) -> Vec<MagicNumber> {
+        let mut found = Vec::new();
+        if doc.doc_type != DocumentType::Code(CodeLanguage::Rust) {
+            return found;
+        }
+
+        let lines: Vec<&str> = doc.content.lines().collect();
+        let code: Vec<char> = mask_rust_literals(&doc.content).chars().collect();
+        let mut line = 1;
+        let mut nesting = 0usize;
+        let mut const_item_nesting: Option<usize> = None;
+        let mut attribute_depth = 0usize;
+        let mut i = 0;
+
+        while i < code.len() {
+            let c = code[i];
+            let previous = code[..i].iter().rev().find(|p| !p.is_whitespace()).copied();
+
+            if c.is_alphabetic() || c == '_' {
+                let start = i;
+                while i < code.len() && (code[i].is_alphanumeric() || code[i] == '_') {
+                    i += 1;
+                }
+                let word: String = code[start..i].iter().collect();
+                if (word == "const" || word == "static") && !matches!(previous, Some('<' | ',' | '*' | '&')) {
+                    let next_word: String = code[i..]
+                        .iter()
+                        .skip_while(|n| n.is_whitespace())
+                        .take_while(|n| n.is_alphanumeric() || **n == '_')
+                        .collect();
+                    if next_word != "fn" {
+                        const_item_nesting = Some(nesting);
+                    }
+                }
+                continue;
+            }
+
+            if c.is_ascii_digit() {
+                let start = i;
+                let radix_prefixed = c == '0' && matches!(code.get(i + 1), Some('x' | 'o' | 'b'));
+                if radix_prefixed {
+                    i += 2;
+                }
+                while i < code.len() && (code[i].is_ascii_alphanumeric() || code[i] == '_') {
+                    if !radix_prefixed && matches!(code[i], 'e' | 'E') && matches!(code.get(i + 1), Some('+' | '-')) {
+                        i += 1;
+                    }
+                    i += 1;
+                    // A fractional part needs a digit after the dot, so ranges like `1..5` stay two literals
+                    if !radix_prefixed && code.get(i) == Some(&'.') && code.get(i + 1).is_some_and(char::is_ascii_digit) {
+                        i += 1;
+                    }
+                }
+
+                // `tuple.0` is a field access, not a literal, but `2..10` ends in one
+                let field_access = start > 0 && code[start - 1] == '.' && (start < 2 || code[start - 2] != '.');
+                if field_access || const_item_nesting.is_some() || attribute_depth > 0 {
+                    continue;
+                }
+
+                let literal: String = code[start..i].iter().collect();
+                if !is_trivial_number(&literal, radix_prefixed) {
+                    let before_minus = code[..start].iter().rev().skip_while(|p| p.is_whitespace()).skip(1).find(|p| !p.is_whitespace());
+                    let negated = previous == Some('-')
+                        && before_minus.is_none_or(|p| "=([{,;:<>!&|+-*/%^".contains(*p));
+                    found.push(MagicNumber {
+                        value: if negated { format!("-{}", literal) } else { literal },
+                        line,
+                        context: lines.get(line - 1).map(|text| text.trim().to_string()).unwrap_or_default(),
+                    });
+                }
+                continue;
+            }
+
+            match c {
+                '\n' => line += 1,
+                ';' if const_item_nesting == Some(nesting) => const_item_nesting = None,
+                '#' => {
+                    let bracket = if code.get(i + 1) == Some(&'!') { i + 2 } else { i + 1 };
+                    if code.get(bracket) == Some(&'[') && attribute_depth == 0 {
+                        attribute_depth = 1;
+                        nesting += 1;
+                        i = bracket;
+                    }
+                }
+                '(' | '[' | '{' => {
+                    nesting += 1;
+                    if c == '[' && attribute_depth > 0 {
+                        attribute_depth += 1;
+                    }
+                }
+                ')' | ']' | '}' => {
+                    nesting = nesting.saturating_sub(1);
+                    if c == ']' && attribute_depth > 0 {
+                        attribute_depth -= 1;
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+
+        found
+    }
+
+    /// Measures how useful the comments of a Rust or JavaScript document are
+    /// Only whole-line comments are considered; trailing comments after code
+    /// count as code.
+    /// # Arguments
+    /// * `doc` - Document to analyze
+    /// # Returns
+    /// Comment report, empty for other document types
+    pub fn measure_comment_quality(doc: &Document) -> CommentQualityReport {
+        const LONG_BLOCK_LINES: usize = 10;
+
+        let mut report = CommentQualityReport::default();
+        if !matches!(doc.doc_type, DocumentType::Code(CodeLanguage::Rust | CodeLanguage::JavaScript)) {
+            return report;
+        }
+
+        let lines: Vec<&str> = doc.content.lines().map(str::trim).collect();
+        let mut comment_lines = 0;
+        let mut code_lines = 0;
+        let mut block_start: Option<usize> = None;
+        let mut line_comment_run = 0;
+
+        for (index, line) in lines.iter().enumerate() {
+            let is_line_comment = block_start.is_none() && line.starts_with("//");
+            let is_comment = if let Some(start) = block_start {
+                if line.contains("*/") {
+                    if index - start + 1 > LONG_BLOCK_LINES {
+                        report.long_comment_blocks += 1;
+                    }
+                    block_start = None;
+                }
+                true
+            } else if let Some(rest) = line.strip_prefix("/*") {
+                if !rest.contains("*/") {
+                    block_start = Some(index);
+                }
+                true
+            } else {
+                is_line_comment
+            };
+
+            if is_line_comment {
+                line_comment_run += 1;
+            } else {
+                if line_comment_run > LONG_BLOCK_LINES {
+                    report.long_comment_blocks += 1;
+                }
+                line_comment_run = 0;
+            }
+
+            if is_comment {
+                comment_lines += 1;
+                report.todo_count += line.matches("TODO").count();
+            } else if !line.is_empty() {
+                code_lines += 1;
+            }
+
+            // Doc comments describe an item for readers of the docs, so only plain comments are checked
+            let plain_comment = line.strip_prefix("//").filter(|text| !text.starts_with(['/', '!']));
+            if let (Some(text), Some(next)) = (plain_comment, lines.get(index + 1)) {
+                if !next.is_empty() && !next.starts_with("//") && !next.starts_with("/*") && restates_code(text, next) {
+                    report.redundant_comments.push(index + 1);
+                }
+            }
+        }
+
+        if let Some(start) = block_start {
+            if lines.len() - start > LONG_BLOCK_LINES {
+                report.long_comment_blocks += 1;
+            }
+        }
+        if line_comment_run > LONG_BLOCK_LINES {
+            report.long_comment_blocks += 1;
+        }
+        if code_lines > 0 {
+            report.comment_to_code_ratio = comment_lines as f64 / code_lines as f64;
+        }
+        report
+    }
+
+    /// Computes Halstead counts of a Rust source document
+    /// Keywords and punctuation are operators, with each bracket pair counted
+    /// once; identifiers and numbers are operands. String and char literals
+    /// and comments are ignored.
+    /// # Arguments
+    /// * `doc` - Document to analyze
+    /// # Returns
+    /// Halstead counts, all zero for documents that are not Rust code
+    pub fn halstead_metrics(doc: &Document) -> HalsteadMetrics {
+        let mut metrics = HalsteadMetrics::default();
+        if doc.doc_type != DocumentType::Code(CodeLanguage::Rust) {
+            return metrics;
+        }
+
+        let mut operators = HashSet::new();
+        let mut operands = HashSet::new();
+        for token in rust_tokens(&doc.content) {
+            match token {
+                RustToken::Operator(text) => {
+                    metrics.total_operators += 1;
+                    operators.insert(text);
+                }
+                RustToken::Operand(text) => {
+                    metrics.total_operands += 1;
+                    operands.insert(text);
+                }
+            }
+        }
+
+        metrics.distinct_operators = operators.len();
+        metrics.distinct_operands = operands.len();
+        let vocabulary = metrics.distinct_operators + metrics.distinct_operands;
+        if vocabulary > 0 {
+            let length = metrics.total_operators + metrics.total_operands;
+            metrics.volume = length as f64 * (vocabulary as f64).log2();
+        }
+        metrics
+    }
+
+    /// Computes the cyclomatic complexity of a Rust source document
+    /// Starts at 1 and adds one per `if`, `while`, looping `for`, `&&`, `||`
+    /// and `?`, plus one per `match` arm after the first.
+    /// # Arguments
+    /// * `doc` - Document to analyze
+    /// # Returns
+    /// Complexity of the whole document, 1 for documents that are not Rust code
+    pub fn cyclomatic_complexity(doc: &Document) -> usize {
+        if doc.doc_type != DocumentType::Code(CodeLanguage::Rust) {
+            return 1;
+        }
+
+        let tokens = rust_tokens(&doc.content);
+        let mut decisions = 0;
+        let mut matches = 0;
+        for (index, token) in tokens.iter().enumerate() {
+            let RustToken::Operator(text) = token else {
+                continue;
+            };
+            match text.as_str() {
+                "if" | "while" | "&&" | "||" | "?" | "=>" => decisions += 1,
+                "match" => matches += 1,
+                // `impl Trait for Type` has no `in` before its body
+                "for" => {
+                    let is_loop = tokens[index + 1..]
+                        .iter()
+                        .take_while(|next| !matches!(next, RustToken::Operator(op) if op == "{" || op == ";"))
+                        .any(|next| matches!(next, RustToken::Operator(op) if op == "in"));
+                    if is_loop {
+                        decisions += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Every match contributed one arrow per arm, but n arms are only n - 1 decisions
+        1 + decisions - matches.min(decisions)
+    }
+
+    /// Computes the Visual Studio maintainability index of a Rust source document
+    /// `MI = 171 - 5.2 * ln(HV) - 0.23 * CC - 16.2 * ln(LOC)` with Halstead
+    /// volume `HV`, cyclomatic complexity `CC` and `LOC` lines that hold code.
+    /// # Arguments
+    /// * `doc` - Document to analyze
+    /// # Returns
+    /// Index clamped to `[0.0, 100.0]`; higher is easier to maintain, and
+    /// documents without code score 100.0
+    pub fn compute_maintainability_index(doc: &Document) -> f64 {
+        if doc.doc_type != DocumentType::Code(CodeLanguage::Rust) {
+            return 100.0;
+        }
+        let lines_of_code = mask_rust_literals(&doc.content)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .count();
+        if lines_of_code == 0 {
+            return 100.0;
+        }
+
+        let volume = Self::halstead_metrics(doc).volume.max(1.0);
+        let complexity = Self::cyclomatic_complexity(doc) as f64;
+        let index = 171.0 - 5.2 * volume.ln() - 0.23 * complexity - 16.2 * (lines_of_code as f64).ln();
+        index.clamp(0.0, 100.0)
+    }
+
+    /// Computes the maintainability index and stores it in the code metadata
+    /// Documents that are not Rust code get no index.
+    /// # Arguments
+    /// * `doc` - Document to analyze and update
+    /// # Returns
+    /// The stored index, or None for documents that are not Rust code
+    pub fn update_maintainability_index(doc: &mut Document) -> Option<f64> {
+        let index = (doc.doc_type == DocumentType::Code(CodeLanguage::Rust))
+            .then(|| Self::compute_maintainability_index(doc));
+        doc.metadata.code.maintainability_index = index;
+        index
+    }
+}
+
+/// Token of Rust source as classified for Halstead metrics
+enum RustToken {
+    Operator(String),
+    Operand(String),
+}
+
+/// Splits Rust source into operator and operand tokens
+/// Closing brackets are dropped so each bracket pair counts once.
+fn rust_tokens(source: &str) -> Vec<RustToken> {
+    const KEYWORDS: [&str; 36] = [
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "fn",
+        "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+        "static", "struct", "super", "trait", "type", "unsafe", "use", "where", "while", "Self", "yield",
+    ];
+    const MULTI_CHAR_OPERATORS: [&str; 21] = [
+        "..=", "<<=", ">>=", "::", "->", "=>", "==", "!=", "<=", ">=", "&&", "||", "+=", "-=", "*=", "/=",
+        "%=", "^=", "|=", "&=", "..",
+    ];
+
+    let code: Vec<char> = mask_rust_literals(source).chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < code.len() {
+        let c = code[i];
+        if c.is_whitespace() || matches!(c, ')' | ']' | '}') {
+            i += 1;
+        } else if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < code.len() && (code[i].is_alphanumeric() || code[i] == '_') {
+                i += 1;
+            }
+            let word: String = code[start..i].iter().collect();
+            if KEYWORDS.contains(&word.as_str()) {
+                tokens.push(RustToken::Operator(word));
+            } else {
+                tokens.push(RustToken::Operand(word));
+            }
+        } else {
+            let rest: String = code[i..(i + 3).min(code.len())].iter().collect();
+            let operator = MULTI_CHAR_OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(*op))
+                .map(|op| op.to_string())
+                .unwrap_or_else(|| c.to_string());
+            i += operator.chars().count();
+            tokens.push(RustToken::Operator(operator));
+        }
+    }
+
+    tokens
+}
+
+/// Checks whether a short comment only names things visible in a line of code
+/// Operators count as the verbs that usually describe them, so
+/// `// increment count` restates `count += 1`.
+fn restates_code(comment: &str, code: &str) -> bool {
+    const OPERATOR_VERBS: [(&str, &[&str]); 6] = [
+        ("+=", &["increment", "increase", "add"]),
+        ("-=", &["decrement", "decrease", "subtract"]),
+        ("*=", &["multiply"]),
+        ("/=", &["divide"]),
+        ("=", &["set", "assign", "store"]),
+        ("(", &["call"]),
+    ];
+    const MAX_WORDS: usize = 6;
+
+    if comment.contains("TODO") || comment.contains("FIXME") {
+        return false;
+    }
+    let words: Vec<String> = normalized_words(comment)
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+        .collect();
+    if words.is_empty() || words.len() > MAX_WORDS {
+        return false;
+    }
+
+    let mut vocabulary: HashSet<String> = HashSet::new();
+    for identifier in code.split(|c: char| !c.is_alphanumeric()).filter(|part| !part.is_empty()) {
+        // Split camelCase so `wordCount` matches a comment mentioning `word count`
+        let mut part = String::new();
+        let mut previous_lower = false;
+        for c in identifier.chars() {
+            if c.is_uppercase() && previous_lower {
+                vocabulary.insert(std::mem::take(&mut part));
+            }
+            previous_lower = c.is_lowercase();
+            part.extend(c.to_lowercase());
+        }
+        vocabulary.insert(part);
+    }
+    for (operator, verbs) in OPERATOR_VERBS {
+        if code.contains(operator) {
+            vocabulary.extend(verbs.iter().map(|verb| verb.to_string()));
+        }
+    }
+
+    words.iter().all(|word| vocabulary.contains(word))
+}
+
+/// Checks whether a numeric literal is 0 or 1, ignoring separators and type suffixes
+fn is_trivial_number(literal: &str, radix_prefixed: bool) -> bool {
+    let digits: String = literal.chars().filter(|c| *c != '_').collect::<String>().to_lowercase();
+    if radix_prefixed {
+        let radix = match &digits[..2] {
+            "0x" => 16,
+            "0o" => 8,
+            _ => 2,
+        };
+        let body = digits[2..].trim_end_matches(|c: char| !c.is_digit(radix));
+        let body = body.split(['i', 'u']).next().unwrap_or(body);
+        return u64::from_str_radix(body, radix).is_ok_and(|value| value <= 1);
+    }
+
+    let number = digits.split(['i', 'u', 'f']).next().unwrap_or(&digits);
+    number.parse::<f64>().is_ok_and(|value| value == 0.0 || value == 1.0)
+}
+
+/// Finds what qualifies a call whose name follows the given code
+/// # Arguments
+/// * `before` - Code up to the called name
+/// # Returns
+/// The separator (`.` for method calls, `::` for paths) and the identifier
+/// before it (empty for receivers such as `f()` that are not identifiers), or
+/// None for unqualified calls; the `..` of a range is not a separator
+fn call_qualifier(before: &[char]) -> Option<(&'static str, String)> {
+    let is_ident = |c: &char| c.is_alphanumeric() || *c == '_';
+    let mut end = before.len();
+    while end > 0 && before[end - 1].is_whitespace() {
+        end -= 1;
+    }
+    let separator = match before[..end] {
+        [.., ':', ':'] => "::",
+        [.., '.', '.'] => return None,
+        [.., '.'] => ".",
+        _ => return None,
+    };
+    end -= separator.len();
+    while end > 0 && before[end - 1].is_whitespace() {
+        end -= 1;
+    }
+    let length = before[..end].iter().rev().take_while(|c| is_ident(c)).count();
+    Some((separator, before[end - length..end].iter().collect()))
+}
+
+/// Blanks out string literals, char literals and comments in Rust source
+/// Masked characters become spaces so offsets and line numbers are kept.
+fn mask_rust_literals(source: &str) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let mut out = String::with_capacity(source.len());
+    let mask = |out: &mut String, c: char| out.push(if c == '\n' { '\n' } else { ' ' });
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                mask(&mut out, chars[i]);
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                mask(&mut out, chars[i]);
+                i += 1;
+            }
+            for _ in i..(i + 2).min(chars.len()) {
+                out.push(' ');
+            }
+            i += 2;
+        } else if c == 'r'
+            && matches!(next, Some('"') | Some('#'))
+            && !chars[..i].last().is_some_and(|p| p.is_alphanumeric() || *p == '_')
+        {
+            let mut hashes = 0;
+            let mut j = i + 1;
+            while chars.get(j) == Some(&'#') {
+                hashes += 1;
+                j += 1;
+            }
+            if chars.get(j) != Some(&'"') {
+                out.push(c);
+                i += 1;
+                continue;
+            }
+            let terminator: Vec<char> = std::iter::once('"').chain(std::iter::repeat_n('#', hashes)).collect();
+            j += 1;
+            while j < chars.len() && !chars[j..].starts_with(&terminator) {
+                j += 1;
+            }
+            let end = (j + terminator.len()).min(chars.len());
+            for &masked in &chars[i..end] {
+                mask(&mut out, masked);
+            }
+            i = end;
+        } else if c == '"' {
+            mask(&mut out, c);
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                let skip = if chars[i] == '\\' { 2 } else { 1 };
+                for &masked in &chars[i..(i + skip).min(chars.len())] {
+                    mask(&mut out, masked);
+                }
+                i += skip;
+            }
+            if i < chars.len() {
+                mask(&mut out, chars[i]);
+                i += 1;
+            }
+        } else if c == '\'' && (next == Some('\\') || chars.get(i + 2) == Some(&'\'')) {
+            // Char literal; a quote not followed by one of these forms is a lifetime
+            let mut j = i + 1;
+            if next == Some('\\') {
+                j += 2;
+            }
+            while j < chars.len() && chars[j] != '\'' {
+                j += 1;
+            }
+            let end = (j + 1).min(chars.len());
+            for &masked in &chars[i..end] {
+                mask(&mut out, masked);
+            }
+            i = end;
+        } else {
+            out.push(c);
+            i += 1;
+        }
+    }
+
+    out
+}
+
+/// Guesses the programming language from keyword density
+/// At least a fifth of the non-empty lines must start with a keyword
+/// of one language for the content to count as source code.
+fn detect_code_language(content: &str) -> Option<CodeLanguage> {
+    const KEYWORDS: [(CodeLanguage, &[&str]); 3] = [
+        (CodeLanguage::Rust, &["fn ", "pub fn ", "struct ", "pub struct ", "impl ", "use ", "let ", "mod "]),
+        (CodeLanguage::Python, &["def ", "class ", "import ", "from ", "elif ", "return "]),
+        (CodeLanguage::JavaScript, &["function ", "const ", "var ", "export ", "import ", "let "]),
+    ];
+
+    let lines: Vec<&str> = content.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+    if lines.is_empty() {
+        return None;
+    }
+
+    let mut best: Option<(CodeLanguage, usize)> = None;
+    for (language, keywords) in KEYWORDS {
+        let hits = lines
+            .iter()
+            .filter(|line| keywords.iter().any(|keyword| line.starts_with(keyword)))
+            .count();
+        if hits * 5 >= lines.len() && best.as_ref().is_none_or(|(_, most)| hits > *most) {
+            best = Some((language, hits));
+        }
+    }
+
+    best.map(|(language, _)| language)
+}
+
+/// Serializes the fields of a document passed to external processors
+fn document_to_json(document: &Document) -> String {
+    let tags: Vec<String> = document.metadata.tags.iter().map(|tag| json_string(tag)).collect();
+    format!(
+        "{{\"id\": {}, \"title\": {}, \"content\": {}, \"doc_type\": {}, \"author\": {}, \"tags\": [{}]}}",
+        json_string(&document.id),
+        json_string(&document.title),
+        json_string(&document.content),
+        json_string(&format!("{:?}", document.doc_type)),
+        json_string(&document.metadata.author),
+        tags.join(", ")
+    )
+}
+
+/// Quotes and escapes a string as a JSON string literal
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Reads a JSON array whose items must all be strings
+fn json_strings<'a>(value: &'a JsonValue, field: &str) -> Result<Vec<&'a str>, String> {
+    match value {
+        JsonValue::Array(items) => items
+            .iter()
+            .map(|item| item.as_str().ok_or_else(|| format!("{} must contain only strings", field)))
+            .collect(),
+        _ => Err(format!("{} must be an array", field)),
+    }
+}
+
+/// Reads a JSON object whose values must all be strings
+fn json_string_map<'a>(value: &'a JsonValue, field: &str) -> Result<Vec<(&'a str, &'a str)>, String> {
+    match value {
+        JsonValue::Object(entries) => entries
+            .iter()
+            .map(|(key, item)| {
+                item.as_str()
+                    .map(|text| (key.as_str(), text))
+                    .ok_or_else(|| format!("{}.{} must be a string", field, key))
+            })
+            .collect(),
+        _ => Err(format!("{} must be an object", field)),
+    }
+}
+
+/// Minimal JSON value used to read external processor output
+#[derive(Debug, Clone, PartialEq)]
+enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// Parses a complete JSON document
+    fn parse(input: &str) -> Result<JsonValue, String> {
+        let mut parser = JsonParser {
+            chars: input.chars().peekable(),
+            depth: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.chars.peek().is_some() {
+            return Err("Trailing characters after JSON value".to_string());
+        }
+        Ok(value)
+    }
+
+    /// Looks up a key in an object
+    fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Gets the string payload of a string value
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// Recursive-descent parser behind `JsonValue::parse`
+/// Nesting is capped at `MAX_DEPTH` so hostile input fails instead of overflowing the stack.
+struct JsonParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    depth: usize,
+}
+
+impl JsonParser<'_> {
+    const MAX_DEPTH: usize = 128;
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.chars.peek(), Some(c) if c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("Expected '{}' but found '{}'", expected, c)),
+            None => Err(format!("Expected '{}' but input ended", expected)),
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: JsonValue) -> Result<JsonValue, String> {
+        for expected in word.chars() {
+            self.expect(expected)?;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<JsonValue, String> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('{') | Some('[') => {
+                if self.depth == Self::MAX_DEPTH {
+                    return Err(format!("JSON nesting exceeds {} levels", Self::MAX_DEPTH));
+                }
+                self.depth += 1;
+                let nested = if self.chars.peek() == Some(&'{') { self.object() } else { self.array() };
+                self.depth -= 1;
+                nested
+            }
+            Some('"') => self.string().map(JsonValue::String),
+            Some('t') => self.literal("true", JsonValue::Bool(true)),
+            Some('f') => self.literal("false", JsonValue::Bool(false)),
+            Some('n') => self.literal("null", JsonValue::Null),
+            Some(c) if *c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) => Err(format!("Unexpected character '{}'", c)),
+            None => Err("Unexpected end of JSON input".to_string()),
+        }
+    }
+
+    fn object(&mut self) -> Result<JsonValue, String> {
+        self.expect('{')?;
+        let mut entries = Vec::new();
+        self.skip_whitespace();
+        if self.chars.peek() == Some(&'}') {
+            self.chars.next();
+            return Ok(JsonValue::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            entries.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(JsonValue::Object(entries)),
+                _ => return Err("Expected ',' or '}' in object".to_string()),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<JsonValue, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.chars.peek() == Some(&']') {
+            self.chars.next();
+            return Ok(JsonValue::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(JsonValue::Array(items)),
+                _ => return Err("Expected ',' or ']' in array".to_string()),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(out),
+                Some('\\') => match self.chars.next() {
+                    Some('n') => out.push('\n'),
+                    Some('r') => out.push('\r'),
+                    Some('t') => out.push('\t'),
+                    Some('b') => out.push('\u{8}'),
+                    Some('f') => out.push('\u{c}'),
+                    Some('u') => {
+                        let code: String = (0..4).filter_map(|_| self.chars.next()).collect();
+                        let value = u32::from_str_radix(&code, 16).map_err(|_| "Invalid \\u escape".to_string())?;
+                        out.push(char::from_u32(value).unwrap_or('\u{fffd}'));
+                    }
+                    Some(c) => out.push(c),
+                    None => return Err("Unterminated escape in string".to_string()),
+                },
+                Some(c) => out.push(c),
+                None => return Err("Unterminated string".to_string()),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<JsonValue, String> {
+        let mut text = String::new();
+        while let Some(c) = self.chars.peek() {
+            if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E') {
+                text.push(*c);
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        text.parse::<f64>()
+            .map(JsonValue::Number)
+            .map_err(|_| format!("Invalid number '{}'", text))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant, SystemTime};
+
+    fn plugin(mode: &str) -> CommandProcessor {
+        let script = std::path::Path::new(file!())
+            .parent()
+            .expect("source file has a directory")
+            .join("../data/command_plugin.sh");
+        let mut processor = CommandProcessor::new(
+            "plugin".to_string(),
+            "sh".to_string(),
+            vec![script.to_string_lossy().into_owned(), mode.to_string(), "{id}".to_string()],
+        );
+        processor.timeout = Duration::from_millis(500);
+        processor
+    }
+
+    fn document() -> Document {
+        Document::new(
+            "doc-1".to_string(),
+            "Title".to_string(),
+            "Some content".to_string(),
+            DocumentType::Text,
+            "Ann".to_string(),
+        )
+    }
+
+    #[test]
+    fn command_processor_writes_document_and_expands_id() {
+        let mut doc = document();
+        assert!(matches!(plugin("echo-input").process(&mut doc), Ok(ProcessingStatus::Completed)));
+
+        let fields = &doc.metadata.custom_fields;
+        assert_eq!(fields["plugin.artifact.id"], "doc-1");
+        assert_eq!(fields["plugin.artifact.input"], document_to_json(&document()));
+    }
+
+    #[test]
+    fn command_processor_applies_metadata_warnings_and_artifacts() {
+        let mut doc = document();
+        assert!(plugin("metadata").process(&mut doc).is_ok());
+
+        assert_eq!(doc.metadata.language, "de");
+        assert_eq!(doc.metadata.tags, vec!["plugin".to_string()]);
+        assert_eq!(doc.metadata.custom_fields["reviewed"], "yes");
+        assert_eq!(doc.metadata.custom_fields["plugin.artifact.summary"], "A summary");
+        assert_eq!(doc.metadata.custom_fields["plugin.warnings"], "short content\nno headings");
+
+        assert!(plugin("completed").process(&mut doc).is_ok());
+        assert!(!doc.metadata.custom_fields.contains_key("plugin.warnings"));
+    }
+
+    #[test]
+    fn command_processor_reports_failures() {
+        let mut doc = document();
+        assert_eq!(plugin("failed").process(&mut doc).unwrap_err(), "bad input");
+        assert_eq!(doc.metadata.custom_fields["plugin.warnings"], "checked 0 rules");
+        assert!(doc.metadata.tags.is_empty());
+
+        let error = plugin("exit-code").process(&mut doc).unwrap_err();
+        assert!(error.contains("something broke"), "{}", error);
+
+        let mut small = plugin("flood");
+        small.max_output_bytes = 1024;
+        let error = small.process(&mut doc).unwrap_err();
+        assert!(error.contains("more than 1024 bytes"), "{}", error);
+    }
+
+    #[test]
+    fn command_processor_kills_child_on_timeout() {
+        let started = Instant::now();
+        let error = plugin("hang").process(&mut document()).unwrap_err();
+        assert!(error.contains("timed out"), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn command_processor_timeout_covers_inherited_pipes() {
+        let started = Instant::now();
+        let error = plugin("background").process(&mut document()).unwrap_err();
+        assert!(error.contains("timed out"), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn command_processor_cancel_stops_current_and_later_runs() {
+        let processor = plugin("hang");
+        let handle = processor.cancel_handle();
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            handle.cancel();
+        });
+
+        let started = Instant::now();
+        let mut slow = processor;
+        slow.timeout = Duration::from_secs(30);
+        let error = slow.process(&mut document()).unwrap_err();
+        canceller.join().expect("canceller thread");
+        assert!(error.contains("cancelled"), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let error = slow.process(&mut document()).unwrap_err();
+        assert!(error.contains("cancelled"), "{}", error);
+    }
+
+    #[test]
+    fn strip_code_blocks_matches_backtick_runs() {
+        let mut doc = document();
+        doc.content = "keep ``a`b`` and `c` but not ``d`\n```\nfn main() {}\n```\nend".to_string();
+        doc.strip_code_blocks();
+
+        assert_eq!(doc.content, "keep  and  but not ``d`\nend");
+        assert_eq!(doc.metadata.custom_fields["stripped_code_blocks"], "1");
+        assert_eq!(doc.metadata.custom_fields["stripped_inline_code"], "2");
+    }
+
+    #[test]
+    fn maintainability_metrics_of_small_function() {
+        let mut doc = document();
+        doc.doc_type = DocumentType::Code(CodeLanguage::Rust);
+        doc.content = "impl Foo for Bar {}\nfn f(x: u8) -> u8 {\n    for i in 0..x { if i > 2 && x < 9 { return i; } }\n    match x { 0 => 1, _ => 2 }\n}\n".to_string();
+
+        assert_eq!(CodeAnalyzer::cyclomatic_complexity(&doc), 5);
+        let halstead = CodeAnalyzer::halstead_metrics(&doc);
+        assert!(halstead.volume > 0.0);
+        let index = CodeAnalyzer::compute_maintainability_index(&doc);
+        assert!((0.0..=100.0).contains(&index));
+        assert_eq!(CodeAnalyzer::compute_maintainability_index(&document()), 100.0);
+
+        assert_eq!(CodeAnalyzer::update_maintainability_index(&mut doc), Some(index));
+        assert_eq!(doc.metadata.code.maintainability_index, Some(index));
+        let mut text = document();
+        assert_eq!(CodeAnalyzer::update_maintainability_index(&mut text), None);
+        assert_eq!(text.metadata.code.maintainability_index, None);
+    }
+
+    #[test]
+    fn find_by_created_since_survives_removal_and_duplicate_ids() {
+        let start = std::time::SystemTime::now();
+        let mut manager = DocumentManager::new();
+        for (id, title) in [("a", "first"), ("b", "second"), ("a", "third")] {
+            let mut doc = document();
+            doc.id = id.to_string();
+            doc.title = title.to_string();
+            manager.add_document(doc);
+        }
+        manager.remove_document("b");
+
+        let titles: Vec<&str> = manager
+            .find_by_created_since(start - std::time::Duration::from_secs(1))
+            .iter()
+            .map(|doc| doc.title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["first", "third"]);
+    }
+
+    #[test]
+    fn citation_extractor_skips_indexing_and_checkboxes() {
+        let content = "- [x] done\n- [ ] todo\nSee [1] and ([Smith2020]).\nlet v = arr[i] + m[0][1];";
+
+        assert_eq!(CitationExtractor::extract(content), vec!["1", "Smith2020"]);
+        assert_eq!(CitationExtractor::extract("[2, 5] at start"), vec!["2", "5"]);
+    }
+
+    #[test]
+    fn generate_slug_keeps_unicode_letters() {
+        let mut doc = document();
+        doc.title = "Ünïcode 2024: Café Notes!".to_string();
+
+        assert_eq!(doc.generate_slug(), "ünïcode-2024-café-notes");
+    }
+
+    #[test]
+    fn bulk_update_metadata_touches_only_changed_documents() {
+        let mut manager = DocumentManager::new();
+        manager.add_document(document());
+        let mut other = document();
+        other.id = "doc-2".to_string();
+        other.metadata.author = "Bob".to_string();
+        manager.add_document(other);
+        let before: Vec<SystemTime> = manager.documents.iter().map(|doc| doc.last_modified).collect();
+        let modified = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&modified);
+        let _handle = manager.watch_for_changes(move |event| recorded.lock().unwrap().push(event.document_id));
+        std::thread::sleep(Duration::from_millis(5));
+
+        manager.bulk_update_metadata(|_, metadata| {
+            if metadata.author == "Ann" {
+                metadata.tags.push("reviewed".to_string());
+            }
+        });
+
+        assert!(manager.documents[0].last_modified > before[0]);
+        assert_eq!(manager.documents[1].last_modified, before[1]);
+        assert_eq!(*modified.lock().unwrap(), ["doc-1"]);
+    }
+
+    #[test]
+    fn topological_sort_handles_duplicate_ids_and_id_prefixes() {
+        let mut manager = DocumentManager::new();
+        let documents = [
+            ("A", "first"),
+            ("A", "second"),
+            ("B", "refs A"),
+            ("doc-1", "see doc-10"),
+            ("doc-10", "see doc-100"),
+            ("doc-100", "mentions doc-1, not doc-1x"),
+        ];
+        for (id, content) in documents {
+            let mut doc = document();
+            doc.id = id.to_string();
+            doc.content = content.to_string();
+            manager.add_document(doc);
+        }
+
+        let graph = DocumentGraph::build(&manager);
+        assert_eq!(graph.references_of("B"), ["A"]);
+        assert_eq!(graph.references_of("doc-100"), ["doc-1"]);
+
+        let cycle = manager.topological_sort_by_references().unwrap_err();
+        let mut cycle_ids = cycle.document_ids.clone();
+        cycle_ids.sort();
+        assert_eq!(cycle_ids, ["doc-1", "doc-10", "doc-100"]);
+
+        manager.documents.last_mut().unwrap().content = "no references".to_string();
+        let sorted = manager.topological_sort_by_references().unwrap();
+        let order: Vec<(&str, &str)> = sorted.iter().map(|doc| (doc.id.as_str(), doc.content.as_str())).collect();
+        assert_eq!(
+            order,
+            [
+                ("A", "first"),
+                ("A", "second"),
+                ("doc-100", "no references"),
+                ("B", "refs A"),
+                ("doc-10", "see doc-100"),
+                ("doc-1", "see doc-10"),
+            ]
+        );
+    }
+
+    #[test]
+    fn processor_tag_changes_notify_subscribers() {
+        let mut manager = DocumentManager::new();
+        let mut doc = document();
+        doc.content = "Shipping #release notes".to_string();
+        manager.add_document(doc);
+        manager.add_processor(Box::new(HashTagExtractor));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        manager.subscribe_to_tag_events(
+            "release",
+            Box::new(move |event| recorded.lock().unwrap().push((event.kind, event.document_id))),
+        );
+
+        manager.process_all_documents();
+        manager.force_reprocess("doc-1", "HashTagExtractor").unwrap();
+
+        assert_eq!(*events.lock().unwrap(), [(TagEventKind::Added, "doc-1".to_string())]);
+    }
+
+    struct RenameAuthor;
+
+    impl DocumentProcessor for RenameAuthor {
+        fn process(&self, document: &mut Document) -> Result<ProcessingStatus, String> {
+            document.metadata.author = "Zed".to_string();
+            Ok(ProcessingStatus::Completed)
+        }
+
+        fn name(&self) -> &str {
+            "RenameAuthor"
+        }
+    }
+
+    #[test]
+    fn processor_author_changes_update_author_index() {
+        let manager = || {
+            let mut manager = DocumentManager::new();
+            manager.add_document(document());
+            manager.add_processor(Box::new(RenameAuthor));
+            manager
+        };
+
+        let mut processed = manager();
+        processed.process_all_documents();
+        assert_eq!(processed.all_authors(), ["Zed"]);
+        assert_eq!(processed.find_by_author("Zed").len(), 1);
+
+        let mut reprocessed = manager();
+        reprocessed.force_reprocess("doc-1", "RenameAuthor").unwrap();
+        assert_eq!(reprocessed.all_authors(), ["Zed"]);
+    }
+
+    struct Backdate;
+
+    impl DocumentProcessor for Backdate {
+        fn process(&self, document: &mut Document) -> Result<ProcessingStatus, String> {
+            document.created_at = std::time::UNIX_EPOCH;
+            Ok(ProcessingStatus::Completed)
+        }
+
+        fn name(&self) -> &str {
+            "Backdate"
+        }
+    }
+
+    #[test]
+    fn processor_created_at_changes_update_created_index() {
+        let mut manager = DocumentManager::new();
+        manager.add_document(document());
+        manager.add_processor(Box::new(Backdate));
+
+        manager.process_all_documents();
+
+        assert!(manager.find_by_created_since(SystemTime::UNIX_EPOCH + Duration::from_secs(1)).is_empty());
+        assert_eq!(manager.find_by_created_since(SystemTime::UNIX_EPOCH - Duration::from_secs(1)).len(), 1);
+    }
+
+    #[test]
+    fn long_running_processors_use_exact_average() {
+        let mut manager = DocumentManager::new();
+        let mut doc = document();
+        for duration_ms in [10, 11] {
+            doc.metadata.processing_history.push(ProcessingRecord {
+                processor_name: "HashTagExtractor".to_string(),
+                status: ProcessingStatus::Completed,
+                timestamp: SystemTime::now(),
+                duration_ms,
+            });
+        }
+        manager.add_document(doc);
+        manager.add_processor(Box::new(HashTagExtractor));
+
+        assert_eq!(manager.find_long_running_processors(10), ["HashTagExtractor"]);
+        assert!(manager.find_long_running_processors(11).is_empty());
+    }
+
+    #[test]
+    fn split_into_pages_ignores_trailing_newline() {
+        let mut doc = document();
+        doc.content = "a\nb\n".to_string();
+
+        let pages: Vec<String> = doc.split_into_pages(2).into_iter().map(|page| page.content).collect();
+
+        assert_eq!(pages, ["a\nb"]);
+    }
+
+    #[test]
+    fn function_call_graph_skips_methods_and_constructors() {
+        let mut doc = document();
+        doc.doc_type = DocumentType::Code(CodeLanguage::Rust);
+        doc.content = "fn contains(text: &str) -> bool { text.contains(\"x\") }\n\
+            fn helper(&self) -> usize { 0 }\n\
+            fn process(&self) -> Result<usize, String> {\n\
+                if contains(\"y\") { return Err(String::new()); }\n\
+                let n = self.helper() + self.len() + (0..count(3)).len() + Self::helper();\n\
+                Ok(n)\n\
+            }\n"
+            .to_string();
+
+        let graph = CodeAnalyzer::function_call_graph(&doc);
+
+        assert!(graph.adjacency["contains"].is_empty());
+        assert_eq!(graph.adjacency["process"], ["contains", "String::new", "count", "helper"]);
+    }
+
+    #[test]
+    fn call_graph_neighborhood_includes_macros() {
+        let mut doc = document();
+        doc.doc_type = DocumentType::Code(CodeLanguage::Rust);
+        doc.content = "fn process(&self) { println!(\"run\"); std::thread::sleep(d); let v = vec![1]; }\n\
+            fn process_all_documents(&self) { self.process(); if a != b {} }\n"
+            .to_string();
+
+        let neighborhood = CodeAnalyzer::function_call_graph(&doc).neighborhood("process", 1, CallDirection::Both);
+
+        let mut names: Vec<&String> = neighborhood.adjacency.keys().collect();
+        names.sort();
+        assert_eq!(names, ["println!", "process", "process_all_documents", "thread::sleep", "vec!"]);
+        assert_eq!(neighborhood.adjacency["process"], ["println!", "thread::sleep", "vec!"]);
+    }
+
+    #[test]
+    fn word_count_histogram_lists_only_filled_buckets() {
+        let mut manager = DocumentManager::new();
+        for content in ["one two", "a b c", &"word ".repeat(1_000_000)] {
+            let mut doc = document();
+            doc.content = content.to_string();
+            doc.metadata.word_count = doc.content.split_whitespace().count();
+            manager.add_document(doc);
+        }
+
+        assert_eq!(manager.word_count_histogram(1).unwrap(), [(2, 1), (3, 1), (1_000_000, 1)]);
+        assert!(manager.word_count_histogram(0).is_err());
+    }
+
+    #[test]
+    fn json_parser_rejects_deep_nesting() {
+        assert!(JsonValue::parse(&"[".repeat(200_000)).is_err());
+        assert!(JsonValue::parse(&format!("{}{}", "[".repeat(128), "]".repeat(128))).is_ok());
+
+        let mut doc = document();
+        doc.content = "[".repeat(200_000);
+        assert_ne!(doc.auto_detect_type(), DocumentType::Json);
+    }
+}